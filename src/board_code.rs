@@ -0,0 +1,376 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::{FieldSettings, GameSettings, Neighborhood, Safety};
+
+/// Version of the binary layout, stored as the first byte of every code.
+const CODE_VERSION: u8 = 1;
+/// Number of bytes in the binary layout, including the checksum.
+const CODE_BYTES: usize = 13;
+/// Number of base32 digits needed to hold [CODE_BYTES] bytes.
+const CODE_DIGITS: usize = (CODE_BYTES * 8).div_ceil(5);
+/// Crockford base32 alphabet, which avoids easily confused letters (I, L, O, U).
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+/// Number of characters between each `-` separator in an encoded code.
+const GROUP_LEN: usize = 4;
+/// Mine density is stored in units of 1/DENSITY_SCALE.
+const DENSITY_SCALE: f32 = 10000.0;
+/// Flag set alongside the safety and neighborhood bits when an exact mine count is
+/// stored instead of the density.
+const MINE_COUNT_FLAG: u8 = 0x80;
+
+/// Everything needed to recreate a specific game, shareable as a short text code.
+///
+/// Given the same board code and the same first click, the same mines are placed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardCode {
+    pub field_size: [usize; 3],
+    pub mine_density: f32,
+    /// Exact number of mines, which takes the place of the density if set.
+    pub mine_count: Option<usize>,
+    pub safety: Safety,
+    pub neighborhood: Neighborhood,
+    pub seed: u32,
+}
+impl BoardCode {
+    /// Overwrite settings so that the next game started is the one described by this code.
+    pub fn apply(&self, field_settings: &mut FieldSettings, game_settings: &mut GameSettings) {
+        *field_settings = FieldSettings {
+            field_size: self.field_size,
+            mine_density: self.mine_density,
+            mine_count: self.mine_count,
+            seed: Some(self.seed),
+        };
+        game_settings.safety = self.safety;
+        game_settings.neighborhood = self.neighborhood;
+    }
+    fn to_bytes(self) -> [u8; CODE_BYTES] {
+        let mut bytes = [0; CODE_BYTES];
+        bytes[0] = CODE_VERSION;
+        for (byte, size) in bytes[1..4].iter_mut().zip(self.field_size) {
+            *byte = size as u8;
+        }
+        let (mines, flag) = match self.mine_count {
+            Some(count) => (count as u16, MINE_COUNT_FLAG),
+            None => ((self.mine_density * DENSITY_SCALE).round() as u16, 0),
+        };
+        bytes[4..6].copy_from_slice(&mines.to_be_bytes());
+        bytes[6] =
+            flag | (safety_to_bits(self.safety) << 4) | neighborhood_to_bits(self.neighborhood);
+        bytes[7..11].copy_from_slice(&self.seed.to_be_bytes());
+        let checksum = fletcher16(&bytes[..11]);
+        bytes[11..].copy_from_slice(&checksum.to_be_bytes());
+        bytes
+    }
+    fn from_bytes(bytes: [u8; CODE_BYTES]) -> Result<Self, CodeError> {
+        let checksum = u16::from_be_bytes([bytes[11], bytes[12]]);
+        if checksum != fletcher16(&bytes[..11]) {
+            return Err(CodeError::ChecksumMismatch);
+        }
+        if bytes[0] != CODE_VERSION {
+            return Err(CodeError::UnsupportedVersion(bytes[0]));
+        }
+        let field_size = [bytes[1] as usize, bytes[2] as usize, bytes[3] as usize];
+        let max_size = FieldSettings::max_size(field_size);
+        if field_size.iter().any(|s| !(1..=max_size).contains(s)) {
+            return Err(CodeError::InvalidSetting("field size"));
+        }
+        let mines = u16::from_be_bytes([bytes[4], bytes[5]]);
+        let (mine_density, mine_count) = if bytes[6] & MINE_COUNT_FLAG != 0 {
+            let num_blocks: usize = field_size.iter().product();
+            if mines == 0 || mines as usize > num_blocks {
+                return Err(CodeError::InvalidSetting("mine count"));
+            }
+            (mines as f32 / num_blocks as f32, Some(mines as usize))
+        } else {
+            if mines == 0 || mines as f32 > DENSITY_SCALE {
+                return Err(CodeError::InvalidSetting("mine density"));
+            }
+            (mines as f32 / DENSITY_SCALE, None)
+        };
+        let safety = safety_from_bits((bytes[6] & !MINE_COUNT_FLAG) >> 4)
+            .ok_or(CodeError::InvalidSetting("first block safety"))?;
+        let neighborhood = neighborhood_from_bits(bytes[6] & 0x0F)
+            .ok_or(CodeError::InvalidSetting("neighborhood"))?;
+        let seed = u32::from_be_bytes([bytes[7], bytes[8], bytes[9], bytes[10]]);
+        Ok(Self {
+            field_size,
+            mine_density,
+            mine_count,
+            safety,
+            neighborhood,
+            seed,
+        })
+    }
+}
+impl Display for BoardCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let chars = encode_base32(&self.to_bytes());
+        let groups: Vec<_> = chars
+            .chunks(GROUP_LEN)
+            .map(|group| String::from_utf8_lossy(group).into_owned())
+            .collect();
+        write!(f, "{}", groups.join("-"))
+    }
+}
+impl FromStr for BoardCode {
+    type Err = CodeError;
+
+    /// Decode a code, ignoring case, whitespace and `-` separators.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| decode_digit(c).ok_or(CodeError::InvalidCharacter(c)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if digits.is_empty() {
+            return Err(CodeError::Empty);
+        }
+        let bytes = decode_base32(&digits).ok_or(CodeError::WrongLength)?;
+        Self::from_bytes(bytes)
+    }
+}
+
+/// Reasons a board code could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeError {
+    /// No code was entered.
+    Empty,
+    /// The code contains a character that is not part of the alphabet.
+    InvalidCharacter(char),
+    /// The code is too long or too short.
+    WrongLength,
+    /// The code does not match its checksum, so it was mistyped or modified.
+    ChecksumMismatch,
+    /// The code was created by an incompatible version of the game.
+    UnsupportedVersion(u8),
+    /// The code describes a setting that is out of range.
+    InvalidSetting(&'static str),
+}
+impl Display for CodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Enter a board code"),
+            Self::InvalidCharacter(c) => write!(f, "Invalid character '{c}' in board code"),
+            Self::WrongLength => write!(f, "Board code has the wrong length"),
+            Self::ChecksumMismatch => write!(f, "Board code is mistyped or damaged"),
+            Self::UnsupportedVersion(v) => write!(f, "Board code version {v} is not supported"),
+            Self::InvalidSetting(setting) => write!(f, "Board code has an invalid {setting}"),
+        }
+    }
+}
+impl std::error::Error for CodeError {}
+
+fn safety_to_bits(safety: Safety) -> u8 {
+    match safety {
+        Safety::Clear => 0,
+        Safety::Safe => 1,
+        Safety::Random => 2,
+    }
+}
+
+fn safety_from_bits(bits: u8) -> Option<Safety> {
+    match bits {
+        0 => Some(Safety::Clear),
+        1 => Some(Safety::Safe),
+        2 => Some(Safety::Random),
+        _ => None,
+    }
+}
+
+fn neighborhood_to_bits(neighborhood: Neighborhood) -> u8 {
+    match neighborhood {
+        Neighborhood::Full => 0,
+        Neighborhood::Edges => 1,
+        Neighborhood::Faces => 2,
+    }
+}
+
+fn neighborhood_from_bits(bits: u8) -> Option<Neighborhood> {
+    match bits {
+        0 => Some(Neighborhood::Full),
+        1 => Some(Neighborhood::Edges),
+        2 => Some(Neighborhood::Faces),
+        _ => None,
+    }
+}
+
+fn fletcher16(data: &[u8]) -> u16 {
+    let (mut sum1, mut sum2) = (0u16, 0u16);
+    for byte in data {
+        sum1 = (sum1 + *byte as u16) % 255;
+        sum2 = (sum2 + sum1) % 255;
+    }
+    (sum2 << 8) | sum1
+}
+
+fn encode_base32(bytes: &[u8; CODE_BYTES]) -> Vec<u8> {
+    let mut chars = Vec::with_capacity(CODE_DIGITS);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            chars.push(ALPHABET[((buffer >> bits) & 0x1F) as usize]);
+        }
+    }
+    if bits > 0 {
+        chars.push(ALPHABET[((buffer << (5 - bits)) & 0x1F) as usize]);
+    }
+    chars
+}
+
+fn decode_base32(digits: &[u8]) -> Option<[u8; CODE_BYTES]> {
+    if digits.len() != CODE_DIGITS {
+        return None;
+    }
+    let mut bytes = [0; CODE_BYTES];
+    let (mut buffer, mut bits, mut n) = (0u32, 0, 0);
+    for digit in digits {
+        buffer = (buffer << 5) | *digit as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes[n] = (buffer >> bits) as u8;
+            n += 1;
+        }
+    }
+    // Unused padding bits must be zero
+    if buffer & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+    Some(bytes)
+}
+
+/// Get the value of a base32 digit, also accepting the characters Crockford
+/// base32 treats as look-alikes.
+fn decode_digit(c: char) -> Option<u8> {
+    let c = match c.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        c => c,
+    };
+    ALPHABET
+        .iter()
+        .position(|a| *a as char == c)
+        .map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code() -> BoardCode {
+        BoardCode {
+            field_size: [10, 12, 7],
+            mine_density: 0.15,
+            mine_count: None,
+            safety: Safety::Safe,
+            neighborhood: Neighborhood::Edges,
+            seed: 0xDEAD_BEEF,
+        }
+    }
+
+    /// Encode `bytes` as a code, with the checksum updated to match.
+    fn encode(mut bytes: [u8; CODE_BYTES]) -> String {
+        let checksum = fletcher16(&bytes[..11]);
+        bytes[11..].copy_from_slice(&checksum.to_be_bytes());
+        String::from_utf8(encode_base32(&bytes)).unwrap()
+    }
+
+    #[test]
+    fn round_trip_with_density() {
+        let code = code();
+        assert_eq!(code.to_string().parse(), Ok(code));
+    }
+
+    #[test]
+    fn round_trip_with_mine_count() {
+        let code = BoardCode {
+            field_size: [30, 16, 1],
+            mine_count: Some(99),
+            mine_density: 99.0 / 480.0,
+            safety: Safety::Random,
+            neighborhood: Neighborhood::Full,
+            ..code()
+        };
+        assert_eq!(code.to_string().parse(), Ok(code));
+    }
+
+    #[test]
+    fn ignores_case_and_separators() {
+        let text = code().to_string().to_lowercase().replace('-', " ");
+        assert_eq!(text.parse(), Ok(code()));
+    }
+
+    #[test]
+    fn rejects_bad_checksum() {
+        let mut bytes = code().to_bytes();
+        bytes[12] ^= 1;
+        let text = String::from_utf8(encode_base32(&bytes)).unwrap();
+        assert_eq!(text.parse::<BoardCode>(), Err(CodeError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut bytes = code().to_bytes();
+        bytes[0] = CODE_VERSION + 1;
+        assert_eq!(
+            encode(bytes).parse::<BoardCode>(),
+            Err(CodeError::UnsupportedVersion(CODE_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_dimensions() {
+        let mut bytes = code().to_bytes();
+        bytes[1] = crate::settings::MAX_FIELD_SIZE as u8 + 1;
+        assert_eq!(
+            encode(bytes).parse::<BoardCode>(),
+            Err(CodeError::InvalidSetting("field size"))
+        );
+        bytes[1] = 0;
+        assert_eq!(
+            encode(bytes).parse::<BoardCode>(),
+            Err(CodeError::InvalidSetting("field size"))
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_mines() {
+        let mut bytes = code().to_bytes();
+        // More mines than the 10x12x7 field has blocks
+        bytes[4..6].copy_from_slice(&841u16.to_be_bytes());
+        bytes[6] |= MINE_COUNT_FLAG;
+        assert_eq!(
+            encode(bytes).parse::<BoardCode>(),
+            Err(CodeError::InvalidSetting("mine count"))
+        );
+        bytes[4..6].copy_from_slice(&0u16.to_be_bytes());
+        assert_eq!(
+            encode(bytes).parse::<BoardCode>(),
+            Err(CodeError::InvalidSetting("mine count"))
+        );
+        bytes[6] &= !MINE_COUNT_FLAG;
+        bytes[4..6].copy_from_slice(&(DENSITY_SCALE as u16 + 1).to_be_bytes());
+        assert_eq!(
+            encode(bytes).parse::<BoardCode>(),
+            Err(CodeError::InvalidSetting("mine density"))
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_length_and_characters() {
+        let text = code().to_string();
+        assert_eq!(
+            text[..text.len() - 1].parse::<BoardCode>(),
+            Err(CodeError::WrongLength)
+        );
+        assert_eq!(
+            format!("{text}U").parse::<BoardCode>(),
+            Err(CodeError::InvalidCharacter('U'))
+        );
+        assert_eq!("".parse::<BoardCode>(), Err(CodeError::Empty));
+    }
+}
@@ -2,11 +2,31 @@ use bevy::prelude::*;
 
 mod block;
 mod camera;
+mod cursor;
+mod deduction;
+mod effects;
+mod highlight;
 mod minefield;
+mod slice;
+mod training;
 
 use block::BlockPlugin;
 use camera::CameraPlugin;
+use cursor::CursorPlugin;
+use deduction::DeductionPlugin;
+use effects::EffectsPlugin;
+use highlight::HighlightPlugin;
 use minefield::FieldPlugin;
+use slice::SlicePlugin;
+use training::TrainingPlugin;
+
+pub use block::{Block, BlockSpacing, HoveredBlock, Marking};
+pub use camera::MainCamera;
+pub use cursor::BlockCursor;
+pub use deduction::LossAnalysis;
+pub use minefield::{Contains, FieldIndex, Minefield};
+pub use slice::{FieldSlice, SliceSide};
+pub use training::Mistakes;
 
 use crate::GameState;
 
@@ -22,8 +42,19 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<GameResult>();
+        app.init_resource::<GameStats>();
         app.add_systems(OnEnter(GameState::GameStart), cleanup);
-        app.add_plugins((BlockPlugin, CameraPlugin, FieldPlugin));
+        app.add_plugins((
+            BlockPlugin,
+            CameraPlugin,
+            CursorPlugin,
+            DeductionPlugin,
+            EffectsPlugin,
+            FieldPlugin,
+            HighlightPlugin,
+            SlicePlugin,
+            TrainingPlugin,
+        ));
     }
 }
 
@@ -31,11 +62,13 @@ pub fn cleanup(
     to_despawn: Query<Entity, With<GamePiece>>,
     mut commands: Commands,
     mut result: ResMut<GameResult>,
+    mut stats: ResMut<GameStats>,
 ) {
     for entity in &to_despawn {
         commands.entity(entity).despawn_recursive();
     }
     *result = GameResult::default();
+    *stats = GameStats::default();
 }
 
 /// When the game ends, what was the result?
@@ -47,4 +80,17 @@ pub enum GameResult {
     Failure,
 }
 
+/// Statistics about the player's moves in the current game.
+#[derive(Default, Resource)]
+pub struct GameStats {
+    /// Number of clicks that cleared or marked a block.
+    pub clicks: usize,
+    /// Number of mines cleared, each costing a life.
+    pub mines_hit: usize,
+    /// Whether this game was played in training mode, so its results are kept apart.
+    pub training: bool,
+    /// Number of blocks wrongly marked or left uncleared, in training mode.
+    pub mistakes: usize,
+}
+
 // TAB - step away ______ -> asfgrdsgg
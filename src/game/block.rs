@@ -2,17 +2,26 @@ use bevy::audio::PlaybackMode;
 use bevy::math::bounding::{Aabb3d, Bounded3d, RayCast3d};
 use bevy::prelude::*;
 
-use super::camera::RayEvent;
+use super::camera::{CursorRay, RayEvent};
 use super::minefield::{Contains, FieldEvent};
-use super::{GamePiece, GameResult, GameState};
-use crate::{FieldSettings, GameAssets};
+use super::{GamePiece, GameResult, GameState, GameStats};
+use crate::{FieldSettings, GameAssets, GameSettings, InputEvent};
 
 pub struct BlockPlugin;
 impl Plugin for BlockPlugin {
     fn build(&self, app: &mut App) {
         // Add Block systems
+        app.init_resource::<BlockSpacing>();
+        app.init_resource::<HoveredBlock>();
         app.add_systems(Startup, create_materials);
         app.add_systems(OnEnter(GameState::GameStart), setup.after(super::cleanup));
+        app.add_systems(
+            Update,
+            (handle_spacing_input, animate_spacing)
+                .chain()
+                .before(handle_ray_events)
+                .run_if(GameState::in_game()),
+        );
         app.add_systems(
             Update,
             handle_ray_events
@@ -21,7 +30,15 @@ impl Plugin for BlockPlugin {
         );
         app.add_systems(
             Update,
-            handle_block_events
+            update_hover
+                .after(super::camera::animate_camera)
+                .after(animate_spacing)
+                .run_if(GameState::in_game()),
+        );
+        app.add_systems(
+            Update,
+            (handle_block_events, animate_reveals)
+                .chain()
                 .after(super::minefield::handle_field_events)
                 .run_if(GameState::in_game()),
         );
@@ -31,10 +48,21 @@ impl Plugin for BlockPlugin {
     }
 }
 
+/// How a hidden block has been marked by the player.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Marking {
+    #[default]
+    None,
+    /// Marked as containing a mine.
+    Mine,
+    /// Marked as possibly containing a mine. Not counted as a mine marking.
+    Maybe,
+}
+
 #[derive(Component)]
 pub struct Block {
-    /// Whether this block has been marked as a mine.
-    marked: bool,
+    /// How this block has been marked by the player.
+    marking: Marking,
     /// Whether this block has been revealed, and thus should
     /// show its number of adjacent mines.
     revealed: Option<Contains>,
@@ -46,7 +74,7 @@ pub struct Block {
 impl Block {
     pub fn new(bb: Aabb3d, index: [usize; 3]) -> Self {
         Self {
-            marked: false,
+            marking: Marking::None,
             revealed: None,
             bb,
             index,
@@ -55,14 +83,86 @@ impl Block {
     pub fn index(&self) -> [usize; 3] {
         self.index
     }
+    pub fn marking(&self) -> Marking {
+        self.marking
+    }
+    pub fn revealed(&self) -> Option<Contains> {
+        self.revealed
+    }
+    /// Whether this block's marking prevents it from being cleared.
+    pub fn is_protected(&self, game_settings: &GameSettings) -> bool {
+        match self.marking {
+            Marking::None => false,
+            Marking::Mine => true,
+            Marking::Maybe => game_settings.protect_maybe_marks,
+        }
+    }
+    /// Whether this block is still hidden and can be cleared or marked.
+    pub fn is_hidden(&self) -> bool {
+        self.revealed.is_none()
+    }
+    /// Whether anything is displayed for this block.
+    fn is_displayed(&self) -> bool {
+        !matches!(
+            self.revealed,
+            Some(Contains::Empty { adjacent_mines: 0 } | Contains::Mine)
+        )
+    }
+}
+
+/// Distance between the centers of adjacent blocks. Spreading blocks apart makes
+/// inner blocks visible and reachable.
+#[derive(Debug, Resource)]
+pub struct BlockSpacing {
+    /// Spacing that blocks are moving towards.
+    pub target: f32,
+    /// Spacing blocks are currently placed at.
+    current: f32,
+}
+impl BlockSpacing {
+    /// Blocks touching each other.
+    pub const MIN: f32 = 1.0;
+    pub const MAX: f32 = 3.0;
+    /// Change in spacing per line scrolled.
+    const SCROLL_SPEED: f32 = 0.1;
+    /// Rate at which the current spacing approaches the target, per second.
+    const ANIMATION_RATE: f32 = 10.0;
+
+    /// Spacing blocks are currently placed at.
+    pub(super) fn current(&self) -> f32 {
+        self.current
+    }
+    /// Where the block at `index` is currently placed.
+    pub(super) fn position(&self, index: [usize; 3], dim: [usize; 3]) -> Vec3 {
+        calculate_position(index, dim, self.current)
+    }
+}
+impl Default for BlockSpacing {
+    fn default() -> Self {
+        Self {
+            target: Self::MIN,
+            current: Self::MIN,
+        }
+    }
+}
+
+/// The block under the pointer, if any.
+#[derive(Debug, Default, Resource, PartialEq)]
+pub struct HoveredBlock {
+    /// Nearest displayed block, including revealed numbers.
+    pub block: Option<(Entity, [usize; 3])>,
+    /// Nearest hidden block, which would be cleared or marked by a click.
+    pub target: Option<(Entity, [usize; 3])>,
 }
 
 #[derive(Debug, Event)]
 pub enum BlockEvent {
     /// Uncover a block, detonating any contained mines.
-    /// Received from the Minefield enitity after checking its contents.
-    Clear(Entity, Contains),
-    /// Mark a block (or unmark if already marked) as containing a mine.
+    /// Received from the Minefield enitity after checking its contents, along with how
+    /// far through a cascade of reveals the block is, from 0 to 1.
+    Clear(Entity, Contains, f32),
+    /// Cycle the marking of a block between unmarked, marked as containing a mine,
+    /// and (if enabled) marked as maybe containing a mine.
     Mark(Entity),
     /// Show the contents of a block after the game has ended.
     EndReveal(Entity, Contains),
@@ -70,15 +170,38 @@ pub enum BlockEvent {
 impl BlockEvent {
     pub fn block_id(&self) -> Entity {
         match self {
-            Self::Clear(e, _) | Self::Mark(e) | Self::EndReveal(e, _) => *e,
+            Self::Clear(e, ..) | Self::Mark(e) | Self::EndReveal(e, _) => *e,
         }
     }
 }
 
+/// A reveal delayed to play as part of a wave, shrinking and fading the hidden block
+/// away before its contents grow in.
+#[derive(Component)]
+pub(super) struct RevealAnimation {
+    /// Seconds since the animation started, negative until it starts.
+    elapsed: f32,
+    adjacent_mines: u8,
+    /// Copy of the hidden material for this block alone, while it fades out.
+    fading: Option<Handle<StandardMaterial>>,
+    /// Whether the revealed contents have been displayed yet.
+    shown: bool,
+}
+impl RevealAnimation {
+    /// Seconds for each block to shrink away and have its contents grow in.
+    const SECS: f32 = 0.25;
+
+    /// Skip to the end, so the block is fully revealed the next time animations run.
+    pub(super) fn finish(&mut self) {
+        self.elapsed = self.elapsed.max(Self::SECS);
+    }
+}
+
 #[derive(Resource)]
 pub(super) struct BlockMaterials {
     hidden: Handle<StandardMaterial>,
     marked: Handle<StandardMaterial>,
+    maybe: Handle<StandardMaterial>,
     blue: Handle<StandardMaterial>,
     green: Handle<StandardMaterial>,
     red: Handle<StandardMaterial>,
@@ -86,10 +209,21 @@ pub(super) struct BlockMaterials {
     purple: Handle<StandardMaterial>,
     mine: Handle<StandardMaterial>,
 }
+impl BlockMaterials {
+    /// Material of a hidden block with the given marking.
+    pub(super) fn hidden(&self, marking: Marking) -> &Handle<StandardMaterial> {
+        match marking {
+            Marking::None => &self.hidden,
+            Marking::Mine => &self.marked,
+            Marking::Maybe => &self.maybe,
+        }
+    }
+}
 
 enum BlockDisplay {
     Hidden,
     Marked,
+    MarkedMaybe,
     Revealed { adjacent_mines: u8 },
     RevealedMine,
     MarkedMine,
@@ -108,6 +242,7 @@ impl BlockDisplay {
         match self {
             Self::Hidden => e.insert((sweeper_objects.block_merged.clone(), mat.hidden.clone())),
             Self::Marked => e.insert(mat.marked.clone()),
+            Self::MarkedMaybe => e.insert(mat.maybe.clone()),
             Self::Revealed { adjacent_mines } => {
                 e.remove::<Handle<Mesh>>();
                 e.remove::<Handle<StandardMaterial>>();
@@ -192,12 +327,16 @@ impl BlockDisplay {
     }
 }
 
-fn calculate_position(index: [usize; 3], dim: [usize; 3]) -> Vec3 {
+fn calculate_position(index: [usize; 3], dim: [usize; 3], spacing: f32) -> Vec3 {
     Vec3::new(
         (index[0] as isize - dim[0] as isize / 2) as f32,
         (index[1] as isize - dim[1] as isize / 2) as f32,
         (index[2] as isize - dim[2] as isize / 2) as f32,
-    )
+    ) * spacing
+}
+
+fn calculate_bb(transform: &Transform) -> Aabb3d {
+    Cuboid::new(1.0, 1.0, 1.0).aabb_3d(transform.translation, transform.rotation)
 }
 
 /// Initialize materials that are re-used between games
@@ -216,6 +355,7 @@ pub(super) fn create_materials(
             ..default()
         }),
         marked: materials.add(Color::RED),
+        maybe: materials.add(Color::YELLOW),
         blue: materials.add(Color::BLUE),
         green: materials.add(Color::GREEN),
         red: materials.add(Color::RED),
@@ -228,6 +368,7 @@ pub(super) fn create_materials(
 /// Setup to be run when the game is started
 pub(super) fn setup(
     field_settings: Res<FieldSettings>,
+    spacing: Res<BlockSpacing>,
     mut commands: Commands,
     block_mat: Res<BlockMaterials>,
     game_assets: Res<GameAssets>,
@@ -235,7 +376,7 @@ pub(super) fn setup(
 ) {
     let mut add_cube = |index, pos| {
         let transform = Transform::from_translation(pos);
-        let bb = Cuboid::new(1.0, 1.0, 1.0).aabb_3d(transform.translation, transform.rotation);
+        let bb = calculate_bb(&transform);
         let block = commands
             .spawn((
                 PbrBundle {
@@ -255,49 +396,146 @@ pub(super) fn setup(
     for i in 0..field_size[0] {
         for j in 0..field_size[1] {
             for k in 0..field_size[2] {
-                let pos = calculate_position([i, j, k], field_size);
+                let pos = calculate_position([i, j, k], field_size, spacing.current);
                 add_cube([i, j, k], pos);
             }
         }
     }
 }
 
+fn handle_spacing_input(
+    mut input_events: EventReader<InputEvent>,
+    mut spacing: ResMut<BlockSpacing>,
+) {
+    for input_event in input_events.read() {
+        if let InputEvent::SpreadBlocks { delta } = input_event {
+            spacing.target = (spacing.target + delta * BlockSpacing::SCROLL_SPEED)
+                .clamp(BlockSpacing::MIN, BlockSpacing::MAX);
+        }
+    }
+}
+
+/// Move blocks smoothly towards the target spacing.
+fn animate_spacing(
+    time: Res<Time>,
+    field_settings: Res<FieldSettings>,
+    mut spacing: ResMut<BlockSpacing>,
+    mut blocks: Query<(&mut Block, &mut Transform)>,
+) {
+    if spacing.current == spacing.target {
+        return;
+    }
+    let step = 1.0 - (-BlockSpacing::ANIMATION_RATE * time.delta_seconds()).exp();
+    spacing.current += (spacing.target - spacing.current) * step;
+    if (spacing.target - spacing.current).abs() < 0.001 {
+        spacing.current = spacing.target;
+    }
+    for (mut block, mut transform) in &mut blocks {
+        transform.translation =
+            calculate_position(block.index, field_settings.field_size, spacing.current);
+        block.bb = calculate_bb(&transform);
+    }
+}
+
 pub(super) fn handle_ray_events(
+    game_settings: Res<GameSettings>,
     mut ray_events: EventReader<RayEvent>,
-    blocks: Query<(Entity, &Block)>,
+    blocks: Query<(Entity, &Block, &Visibility)>,
     mut block_events: EventWriter<BlockEvent>,
     mut field_events: EventWriter<FieldEvent>,
+    mut stats: ResMut<GameStats>,
 ) {
     for ray_event in ray_events.read() {
         match ray_event {
             RayEvent::ClearBlock(ray) => {
-                if let Some((block, _entity, index)) = raycast_blocks(*ray, &blocks) {
-                    if !block.marked {
+                if let Some((block, _entity, index)) =
+                    raycast_blocks(*ray, &blocks, Block::is_hidden)
+                {
+                    if !block.is_protected(&game_settings) {
+                        stats.clicks += 1;
                         debug!("Send FieldEvent::ClearBlock");
                         field_events.send(FieldEvent::ClearBlock(index));
                     }
                 }
             }
             RayEvent::MarkBlock(ray) => {
-                if let Some((_block, entity, _index)) = raycast_blocks(*ray, &blocks) {
+                if let Some((_block, entity, _index)) =
+                    raycast_blocks(*ray, &blocks, Block::is_hidden)
+                {
+                    stats.clicks += 1;
                     debug!("Send BlockEvent::Mark");
                     block_events.send(BlockEvent::Mark(entity));
                 }
             }
+            RayEvent::ChordBlock(ray) => {
+                let Some((center, _entity, _index)) =
+                    raycast_blocks(*ray, &blocks, Block::is_displayed)
+                else {
+                    continue;
+                };
+                let adjacent = blocks.iter().map(|(_, block, _)| block);
+                if let Some(targets) = chord_targets(center, adjacent, &game_settings) {
+                    stats.clicks += 1;
+                    for index in targets {
+                        debug!("Send FieldEvent::ClearBlock");
+                        field_events.send(FieldEvent::ClearBlock(index));
+                    }
+                }
+            }
         }
     }
 }
 
+/// Get the blocks to clear when chording on `center`, or `None` unless `center` is
+/// a revealed number with exactly that many adjacent blocks marked as mines.
+pub(super) fn chord_targets<'a>(
+    center: &Block,
+    blocks: impl Iterator<Item = &'a Block>,
+    game_settings: &GameSettings,
+) -> Option<Vec<[usize; 3]>> {
+    let Some(Contains::Empty { adjacent_mines }) = center.revealed else {
+        return None;
+    };
+    let adjacent: Vec<_> = blocks
+        .filter(|block| {
+            let offset = [0, 1, 2].map(|n| block.index[n] as isize - center.index[n] as isize);
+            game_settings.neighborhood.contains(offset)
+        })
+        .collect();
+    let marked = adjacent
+        .iter()
+        .filter(|block| block.marking == Marking::Mine)
+        .count();
+    // Only chord once the number is satisfied by markings
+    if marked != adjacent_mines as usize {
+        return None;
+    }
+    Some(
+        adjacent
+            .into_iter()
+            .filter(|block| block.is_hidden() && !block.is_protected(game_settings))
+            .map(|block| block.index)
+            .collect(),
+    )
+}
+
+/// Find the nearest visible block hit by `ray` among those that are `pickable`.
 fn raycast_blocks<'a>(
     ray: Ray3d,
-    blocks: &'a Query<(Entity, &Block)>,
+    blocks: &'a Query<(Entity, &Block, &Visibility)>,
+    pickable: impl Fn(&Block) -> bool,
 ) -> Option<(&'a Block, Entity, [usize; 3])> {
-    let cast = RayCast3d::from_ray(ray, 100.0);
+    // Reach past the far side of the field, however far away the ray starts
+    let field_reach = blocks
+        .iter()
+        .map(|(_, block, _)| ((block.bb.min + block.bb.max) / 2.0).length())
+        .fold(0.0, f32::max);
+    let cast = RayCast3d::from_ray(ray, ray.origin.length() + field_reach + 1.0);
 
     let mut hits: Vec<_> = blocks
         .iter()
-        .filter(|(_, block)| block.revealed.is_none())
-        .filter_map(|(entity, block)| {
+        .filter(|(_, block, visibility)| pickable(block) && **visibility != Visibility::Hidden)
+        .filter_map(|(entity, block, _)| {
             cast.aabb_intersection_at(&block.bb)
                 .map(|dist| (dist, entity, block))
         })
@@ -311,11 +549,31 @@ fn raycast_blocks<'a>(
 
     let (dist, hit, block) = hits.first()?;
     let index = block.index;
-    debug!("Block {hit:?} {index:?} hit at {dist}");
+    trace!("Block {hit:?} {index:?} hit at {dist}");
     Some((block, *hit, index))
 }
 
+/// Find the block under the pointer every frame.
+pub(super) fn update_hover(
+    cursor_ray: Res<CursorRay>,
+    blocks: Query<(Entity, &Block, &Visibility)>,
+    mut hovered: ResMut<HoveredBlock>,
+) {
+    let raycast = |pickable: fn(&Block) -> bool| {
+        cursor_ray
+            .ray
+            .and_then(|ray| raycast_blocks(ray, &blocks, pickable))
+            .map(|(_block, entity, index)| (entity, index))
+    };
+    hovered.set_if_neq(HoveredBlock {
+        block: raycast(Block::is_displayed),
+        target: raycast(Block::is_hidden),
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(super) fn handle_block_events(
+    game_settings: Res<GameSettings>,
     mut commands: Commands,
     mut block_events: EventReader<BlockEvent>,
     mut blocks: Query<&mut Block>,
@@ -323,6 +581,7 @@ pub(super) fn handle_block_events(
     game_assets: Res<GameAssets>,
     mut next_state: ResMut<NextState<GameState>>,
     mut game_result: ResMut<GameResult>,
+    mut stats: ResMut<GameStats>,
 ) {
     let mut any_blocks_cleared = false;
     for event in block_events.read() {
@@ -335,7 +594,7 @@ pub(super) fn handle_block_events(
             }
         };
         match event {
-            BlockEvent::Clear(entity, contains) => {
+            BlockEvent::Clear(entity, contains, wave) => {
                 debug!("Revealed block {entity:?}");
                 block.revealed = Some(*contains);
                 any_blocks_cleared = true;
@@ -347,8 +606,30 @@ pub(super) fn handle_block_events(
                             *entity,
                             &mut commands,
                         );
-                        *game_result = GameResult::Failure;
-                        next_state.set(GameState::GameOver);
+                        stats.mines_hit += 1;
+                        if stats.mines_hit < game_settings.lives {
+                            info!(
+                                "Mine hit, {} lives remaining",
+                                game_settings.lives - stats.mines_hit
+                            );
+                            block.marking = Marking::Mine;
+                        } else {
+                            *game_result = GameResult::Failure;
+                            next_state.set(GameState::GameOver);
+                        }
+                    }
+                    // Blocks revealed by a cascade are only displayed once the wave reaches them
+                    Contains::Empty { adjacent_mines }
+                        if *wave > 0.0
+                            && game_settings.reveal_duration > 0.0
+                            && !game_settings.reduced_motion =>
+                    {
+                        commands.entity(*entity).insert(RevealAnimation {
+                            elapsed: -wave * game_settings.reveal_duration,
+                            adjacent_mines,
+                            fading: None,
+                            shown: false,
+                        });
                     }
                     Contains::Empty { adjacent_mines } => BlockDisplay::Revealed { adjacent_mines }
                         .spawn(&game_assets, &block_mat, *entity, &mut commands),
@@ -366,7 +647,7 @@ pub(super) fn handle_block_events(
                             &mut commands,
                         );
                     }
-                    Contains::Mine if block.marked => {
+                    Contains::Mine if block.marking == Marking::Mine => {
                         BlockDisplay::MarkedMine.spawn(
                             &game_assets,
                             &block_mat,
@@ -382,22 +663,34 @@ pub(super) fn handle_block_events(
                             &mut commands,
                         );
                     }
+                    // Already displayed, or will be once its reveal animation plays
+                    Contains::Empty { .. } if block.revealed.is_some() => {}
                     Contains::Empty { adjacent_mines } => BlockDisplay::Revealed { adjacent_mines }
                         .spawn(&game_assets, &block_mat, *entity, &mut commands),
                 }
                 block.revealed = Some(*contains);
             }
-            BlockEvent::Mark(entity) => match block.marked {
-                true => {
-                    debug!("Unmark block {entity:?}");
-                    block.marked = false;
-                    BlockDisplay::Hidden.spawn(&game_assets, &block_mat, *entity, &mut commands);
-                }
-                false => {
+            BlockEvent::Mark(entity) => match block.marking {
+                Marking::None => {
                     debug!("Mark block {entity:?}");
-                    block.marked = true;
+                    block.marking = Marking::Mine;
                     BlockDisplay::Marked.spawn(&game_assets, &block_mat, *entity, &mut commands);
                 }
+                Marking::Mine if game_settings.maybe_marks => {
+                    debug!("Mark block {entity:?} as maybe");
+                    block.marking = Marking::Maybe;
+                    BlockDisplay::MarkedMaybe.spawn(
+                        &game_assets,
+                        &block_mat,
+                        *entity,
+                        &mut commands,
+                    );
+                }
+                Marking::Mine | Marking::Maybe => {
+                    debug!("Unmark block {entity:?}");
+                    block.marking = Marking::None;
+                    BlockDisplay::Hidden.spawn(&game_assets, &block_mat, *entity, &mut commands);
+                }
             },
         }
     }
@@ -412,6 +705,57 @@ pub(super) fn handle_block_events(
     }
 }
 
+/// Play the reveal animations of blocks as the wave of a cascade reaches them.
+fn animate_reveals(
+    time: Res<Time>,
+    mut commands: Commands,
+    block_mat: Res<BlockMaterials>,
+    game_assets: Res<GameAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut blocks: Query<(Entity, &mut Transform, &mut RevealAnimation)>,
+) {
+    for (entity, mut transform, mut animation) in &mut blocks {
+        animation.elapsed += time.delta_seconds();
+        let progress = animation.elapsed / RevealAnimation::SECS;
+        if progress <= 0.0 {
+            continue;
+        }
+        if progress < 0.5 {
+            let fade = 1.0 - progress * 2.0;
+            transform.scale = Vec3::splat(fade);
+            let fading = animation.fading.get_or_insert_with(|| {
+                let mut material = materials
+                    .get(&block_mat.hidden)
+                    .cloned()
+                    .unwrap_or_default();
+                material.alpha_mode = AlphaMode::Blend;
+                let fading = materials.add(material);
+                commands.entity(entity).insert(fading.clone());
+                fading
+            });
+            if let Some(material) = materials.get_mut(fading.id()) {
+                material.base_color.set_a(fade);
+            }
+            continue;
+        }
+        if !animation.shown {
+            animation.shown = true;
+            // Displaying the contents replaces the faded material, so it can be dropped
+            animation.fading = None;
+            BlockDisplay::Revealed {
+                adjacent_mines: animation.adjacent_mines,
+            }
+            .spawn(&game_assets, &block_mat, entity, &mut commands);
+        }
+        if progress < 1.0 {
+            transform.scale = Vec3::splat(progress * 2.0 - 1.0);
+        } else {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<RevealAnimation>();
+        }
+    }
+}
+
 #[cfg(feature = "debug-draw")]
 fn block_gizmos(mut gizmos: Gizmos, blocks: Query<&Transform, With<Block>>) {
     for tf in blocks.iter() {
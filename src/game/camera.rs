@@ -1,114 +1,446 @@
-use std::f32::consts::{PI, TAU};
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
 use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy::window::{PrimaryWindow, WindowResized};
 
-use crate::{input::ScreenPosition, GameState, InputEvent};
+use crate::{input::ScreenPosition, FieldSettings, GameSettings, GameState, InputEvent};
 
-use super::GamePiece;
+use super::block::{Block, BlockSpacing, HoveredBlock};
+use super::highlight::Frontier;
+use super::{BlockCursor, GamePiece};
 
 pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         // Add Camera systems
         app.add_systems(OnEnter(GameState::GameStart), spawn.after(super::cleanup));
-        app.add_systems(Update, camera_controls.run_if(GameState::in_game()));
+        app.add_systems(
+            Update,
+            (
+                apply_projection,
+                frame_field,
+                camera_controls,
+                animate_camera,
+            )
+                .chain()
+                .run_if(GameState::in_game()),
+        );
         app.add_event::<RayEvent>();
+        app.init_resource::<CursorRay>();
         app.insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)));
         #[cfg(feature = "debug-draw")]
         app.add_systems(Update, cursor_ray_gizmo.run_if(GameState::playable()));
     }
 }
 
+/// Position of a camera orbiting around a point, looking at it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Orbit {
+    /// Point the camera orbits around and looks at.
+    pub target: Vec3,
+    /// Rotation around the vertical axis, in radians.
+    pub yaw: f32,
+    /// Rotation above (negative) or below (positive) the target, in radians.
+    pub pitch: f32,
+    /// Distance from the target.
+    pub distance: f32,
+}
+impl Orbit {
+    /// Largest pitch allowed, just short of looking straight up or down.
+    const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+    fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0)
+    }
+    fn transform(&self) -> Transform {
+        let rotation = self.rotation();
+        Transform {
+            translation: self.target + rotation * Vec3::Z * self.distance,
+            rotation,
+            ..default()
+        }
+    }
+    /// Look at the target from `direction`, turning the shortest way around.
+    fn look_from(&mut self, direction: Vec3) {
+        let direction = direction.normalize();
+        self.pitch = (-direction.y.asin()).clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+        // Looking straight up or down keeps the current heading
+        if direction.x != 0.0 || direction.z != 0.0 {
+            let yaw = direction.x.atan2(direction.z);
+            self.yaw = yaw + ((self.yaw - yaw) / TAU).round() * TAU;
+        }
+    }
+    /// Move `fraction` of the way towards `goal`.
+    fn ease_towards(&mut self, goal: &Self, fraction: f32) {
+        self.target = self.target.lerp(goal.target, fraction);
+        self.yaw += (goal.yaw - self.yaw) * fraction;
+        self.pitch += (goal.pitch - self.pitch) * fraction;
+        self.distance += (goal.distance - self.distance) * fraction;
+    }
+}
+impl Default for Orbit {
+    /// Looking down at the field diagonally.
+    fn default() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            yaw: PI / 4.0,
+            pitch: -(1.0 / 3.0f32.sqrt()).asin(),
+            distance: 10.0,
+        }
+    }
+}
+
+/// Camera controller. Input moves the goal orbit, and the camera eases towards it.
 #[derive(Component)]
 pub struct MainCamera {
+    /// Orbit the camera is moving towards.
+    pub goal: Orbit,
+    /// Orbit the camera is currently at.
+    current: Orbit,
+    /// Rotation speed in radians per second of yaw and pitch, which continues
+    /// after input stops if inertia is enabled.
+    velocity: Vec2,
     zoom_speed: f32,
     zoom_limit_near: f32,
     zoom_limit_far: f32,
+    /// Whether the field is flat, so the camera is locked looking straight at it
+    /// with an orthographic projection.
+    flat: bool,
+    /// Whether the camera is still at the distance that fits the whole field in view,
+    /// and should be moved to keep it in view if the window changes.
+    framed: bool,
 }
 impl Default for MainCamera {
     fn default() -> Self {
         Self {
+            goal: Orbit::default(),
+            current: Orbit::default(),
+            velocity: Vec2::ZERO,
             zoom_speed: 1.0,
             zoom_limit_near: 1.0,
             zoom_limit_far: 20.0,
+            flat: false,
+            framed: true,
         }
     }
 }
+impl MainCamera {
+    /// Vertical field of view of the perspective projection, in radians.
+    const FOV: f32 = PI / 4.0;
+
+    /// Move the camera to its goal immediately.
+    fn snap_to_goal(&mut self) {
+        self.current = self.goal;
+        self.velocity = Vec2::ZERO;
+    }
+}
+
+/// Extra space around the field when it is framed by the camera.
+const FRAMING_MARGIN: f32 = 1.1;
+/// How far past the framing distance of a fully spread field the camera can zoom out.
+const ZOOM_OUT_MARGIN: f32 = 1.5;
+
+/// Radius of a sphere around the center of the field that contains every block.
+fn field_radius(field_size: [usize; 3], spacing: f32) -> f32 {
+    // Blocks are offset by up to half a block from the center, plus half a block width
+    Vec3::from(field_size.map(|size| size as f32 / 2.0 * spacing + 0.5)).length()
+}
+
+/// Distance from `target` at which an orthographic camera is just outside the field, where
+/// its distance does not change the view. The target may be anywhere after panning or
+/// focusing, so this reaches past the far side of the field from it.
+fn orthographic_distance(target: Vec3, field_radius: f32) -> f32 {
+    target.length() + field_radius + 1.0
+}
+
+/// Distance from the center of a sphere at which it just fits in the camera's view.
+fn framing_distance(radius: f32, fov: f32, aspect_ratio: f32) -> f32 {
+    let half_fov_vertical = fov / 2.0;
+    let half_fov_horizontal = (half_fov_vertical.tan() * aspect_ratio).atan();
+    radius / half_fov_vertical.min(half_fov_horizontal).sin()
+}
+
+/// Ray from the camera through the pointer, kept up to date as the camera moves.
+#[derive(Default, Resource)]
+pub struct CursorRay {
+    /// Last known pointer position, if it is over the field.
+    position: Option<ScreenPosition>,
+    pub ray: Option<Ray3d>,
+}
 
 #[derive(Event)]
+#[allow(clippy::enum_variant_names)]
 pub enum RayEvent {
     ClearBlock(Ray3d),
     MarkBlock(Ray3d),
+    ChordBlock(Ray3d),
 }
 
-pub(super) fn spawn(mut commands: Commands) {
-    let zoom = 10.0;
-    let translation = Vec3::ONE.normalize() * zoom;
+pub(super) fn spawn(mut commands: Commands, field_settings: Res<FieldSettings>) {
+    let mut main_camera = MainCamera::default();
+    if let Some(axis) = FieldSettings::flat_axis(field_settings.field_size) {
+        main_camera.flat = true;
+        let goal = &mut main_camera.goal;
+        if axis == 1 {
+            // Straight down, which is not allowed when rotating freely
+            goal.yaw = 0.0;
+            goal.pitch = -FRAC_PI_2;
+        } else {
+            goal.look_from(Vec3::AXES[axis]);
+        }
+        main_camera.snap_to_goal();
+    }
 
     commands.spawn((
         Camera3dBundle {
-            transform: Transform::from_translation(translation).looking_at(Vec3::ZERO, Vec3::Y),
+            transform: main_camera.current.transform(),
             ..Default::default()
         },
-        MainCamera::default(),
+        main_camera,
         GamePiece,
     ));
 }
 
+/// Fit the whole field in view for a new camera, and update zoom limits and framing
+/// whenever the window is resized.
+fn frame_field(
+    field_settings: Res<FieldSettings>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    mut resize_events: EventReader<WindowResized>,
+    mut camera: Query<&mut MainCamera>,
+    new_camera: Query<(), Added<MainCamera>>,
+) {
+    let resized = resize_events.read().count() > 0;
+    if !resized && new_camera.is_empty() {
+        return;
+    }
+    let Ok(mut main_camera) = camera.get_single_mut() else {
+        return;
+    };
+    let window = primary_window.single();
+    if window.height() <= 0.0 {
+        return;
+    }
+    let aspect_ratio = window.width() / window.height();
+    // Orthographic zoom matches the view at the same distance with perspective
+    let fov = MainCamera::FOV;
+    let field_size = field_settings.field_size;
+    let distance = |spacing| framing_distance(field_radius(field_size, spacing), fov, aspect_ratio);
+    // Zoom in no further than a single block filling the view
+    let block_radius = Vec3::splat(0.5).length();
+    main_camera.zoom_limit_near = framing_distance(block_radius, fov, aspect_ratio);
+    main_camera.zoom_limit_far = distance(BlockSpacing::MAX) * ZOOM_OUT_MARGIN;
+    main_camera.goal.distance = if main_camera.framed {
+        distance(BlockSpacing::MIN) * FRAMING_MARGIN
+    } else {
+        let (near, far) = (main_camera.zoom_limit_near, main_camera.zoom_limit_far);
+        main_camera.goal.distance.clamp(near, far)
+    };
+    debug!("Frame field at distance {}", main_camera.goal.distance);
+    // A new camera starts framed, rather than moving into place
+    if !new_camera.is_empty() {
+        main_camera.snap_to_goal();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(super) fn camera_controls(
+    time: Res<Time>,
+    hovered: Res<HoveredBlock>,
+    mut cursor: ResMut<BlockCursor>,
+    mut frontier: ResMut<Frontier>,
+    blocks: Query<(&Block, &Transform), Without<MainCamera>>,
     mut input_events: EventReader<InputEvent>,
-    mut camera_transform: Query<(&Camera, &MainCamera, &mut Transform)>,
+    mut camera_transform: Query<(&Camera, &mut MainCamera, &Transform)>,
     mut ray_events: EventWriter<RayEvent>,
+    mut cursor_ray: ResMut<CursorRay>,
 ) {
-    let (camera, main_camera, mut transform) = camera_transform.single_mut();
+    let (camera, mut main_camera, transform) = camera_transform.single_mut();
+    let flat = main_camera.flat;
+    let mut rotation = Vec2::ZERO;
     for input_event in input_events.read() {
+        let goal = &mut main_camera.goal;
         match input_event {
+            InputEvent::PointAt(cursor_pos) => {
+                cursor_ray.position = *cursor_pos;
+            }
             InputEvent::RotateCamera { delta } => {
-                let delta_x = delta.x * TAU;
-                let delta_y = delta.y * PI;
-                // Rotate around local X axis and global Y axis
-                let camera_tilt = transform.up().dot(Vec3::Y);
-                debug!("Camera tilt: {camera_tilt}");
-                let x_rot = Quat::from_axis_angle(
-                    Vec3::Y,
-                    if camera_tilt > 0.0 { -delta_x } else { delta_x },
-                );
-                let y_rot = Quat::from_axis_angle(*transform.local_x(), -delta_y);
-                transform.rotate_around(Vec3::ZERO, x_rot);
-                transform.rotate_around(Vec3::ZERO, y_rot);
-                // Fix the odd tilt we get sometimes
-                let camera_tilt = transform.up().dot(Vec3::Y);
-                if camera_tilt > 0.01 {
-                    *transform = transform.looking_at(Vec3::ZERO, Vec3::Y);
-                }
-                if camera_tilt < -0.01 {
-                    *transform = transform.looking_at(Vec3::ZERO, Vec3::NEG_Y);
-                }
+                // Dragging down raises the camera, and dragging right moves it left
+                rotation -= Vec2::new(delta.x * TAU, delta.y * PI);
             }
             InputEvent::ZoomCamera { delta } => {
-                let zoom = *delta * transform.translation * main_camera.zoom_speed * -0.1;
-                let new_translation = transform.translation + zoom;
-                let zoom_dist = new_translation.distance(Vec3::ZERO);
-                if zoom_dist > main_camera.zoom_limit_near && zoom_dist < main_camera.zoom_limit_far
-                {
-                    *transform = transform.with_translation(new_translation);
-                }
+                let zoom_speed = main_camera.zoom_speed;
+                let (near, far) = (main_camera.zoom_limit_near, main_camera.zoom_limit_far);
+                let goal = &mut main_camera.goal;
+                goal.distance =
+                    (goal.distance * (1.0 - *delta * zoom_speed * 0.1)).clamp(near, far);
+                main_camera.framed = false;
+            }
+            InputEvent::PanCamera { delta } => {
+                // Move further when zoomed out, so the field moves along with the pointer
+                let offset =
+                    (*transform.left() * delta.x + *transform.up() * delta.y) * goal.distance;
+                goal.target += offset;
+            }
+            // Flat fields are always viewed straight on
+            InputEvent::SnapView { .. } if flat => {}
+            InputEvent::SnapView { direction } => {
+                debug!("Snap view to {direction}");
+                goal.look_from(direction.as_vec3());
+                main_camera.velocity = Vec2::ZERO;
+            }
+            InputEvent::FocusBlock => {
+                // Focus on the block being pointed at, or the block cursor
+                let focused = hovered
+                    .target
+                    .or(hovered.block)
+                    .and_then(|(entity, _)| blocks.get(entity).ok())
+                    .or_else(|| {
+                        cursor.index.and_then(|index| {
+                            blocks.iter().find(|(block, _)| block.index() == index)
+                        })
+                    });
+                // Focusing on nothing returns to the center of the field
+                goal.target = focused.map_or(Vec3::ZERO, |(_, block)| block.translation);
+                debug!("Focus camera on {}", goal.target);
+            }
+            InputEvent::NextFrontier => {
+                let Some(index) = frontier.cycle() else {
+                    continue;
+                };
+                let Some((_, block)) = blocks.iter().find(|(block, _)| block.index() == index)
+                else {
+                    continue;
+                };
+                // Select it as well, so it can be cleared or marked from the keyboard
+                cursor.index = Some(index);
+                goal.target = block.translation;
+                debug!("Focus camera on frontier block {index:?}");
             }
             InputEvent::ClearBlock(cursor_pos) => {
-                if let Some(ray) = get_cursor_ray(camera, &transform, *cursor_pos) {
+                if let Some(ray) = get_cursor_ray(camera, transform, *cursor_pos) {
                     debug!("Send RayEvent::ClearBlock");
                     ray_events.send(RayEvent::ClearBlock(ray));
                 }
             }
             InputEvent::MarkBlock(cursor_pos) => {
-                if let Some(ray) = get_cursor_ray(camera, &transform, *cursor_pos) {
+                if let Some(ray) = get_cursor_ray(camera, transform, *cursor_pos) {
                     debug!("Send RayEvent::MarkBlock");
                     ray_events.send(RayEvent::MarkBlock(ray));
                 }
             }
+            InputEvent::ChordBlock(cursor_pos) => {
+                if let Some(ray) = get_cursor_ray(camera, transform, *cursor_pos) {
+                    debug!("Send RayEvent::ChordBlock");
+                    ray_events.send(RayEvent::ChordBlock(ray));
+                }
+            }
             _ => {}
         }
     }
+    if flat {
+        return;
+    }
+    let dt = time.delta_seconds();
+    if rotation != Vec2::ZERO {
+        main_camera.goal.yaw += rotation.x;
+        main_camera.goal.pitch += rotation.y;
+        if dt > 0.0 {
+            main_camera.velocity = rotation / dt;
+        }
+    } else {
+        // Keep spinning from the last input, if inertia is enabled
+        let velocity = main_camera.velocity;
+        main_camera.goal.yaw += velocity.x * dt;
+        main_camera.goal.pitch += velocity.y * dt;
+    }
+    let goal = &mut main_camera.goal;
+    goal.pitch = goal.pitch.clamp(-Orbit::MAX_PITCH, Orbit::MAX_PITCH);
+}
+
+/// Switch between perspective and orthographic projection when the setting changes.
+/// Flat fields are always orthographic, leaving the setting for the next 3D field.
+fn apply_projection(
+    mut game_settings: ResMut<GameSettings>,
+    mut input_events: EventReader<InputEvent>,
+    mut camera: Query<(&mut Projection, &MainCamera)>,
+    new_camera: Query<(), Added<MainCamera>>,
+) {
+    let flat = camera
+        .get_single()
+        .is_ok_and(|(_, main_camera)| main_camera.flat);
+    for input_event in input_events.read() {
+        if let InputEvent::ToggleProjection = input_event {
+            if !flat {
+                game_settings.orthographic = !game_settings.orthographic;
+            }
+        }
+    }
+    if !game_settings.is_changed() && new_camera.is_empty() {
+        return;
+    }
+    let Ok((mut projection, _)) = camera.get_single_mut() else {
+        return;
+    };
+    let use_orthographic = game_settings.orthographic || flat;
+    let orthographic = matches!(*projection, Projection::Orthographic(_));
+    if orthographic == use_orthographic {
+        return;
+    }
+    debug!("Orthographic projection: {use_orthographic}");
+    *projection = if use_orthographic {
+        // Scaled to match the camera distance in animate_camera
+        Projection::Orthographic(OrthographicProjection::default())
+    } else {
+        Projection::Perspective(PerspectiveProjection {
+            fov: MainCamera::FOV,
+            ..default()
+        })
+    };
+}
+
+/// Ease the camera towards its goal, and update the ray through the pointer.
+pub(super) fn animate_camera(
+    time: Res<Time>,
+    game_settings: Res<GameSettings>,
+    field_settings: Res<FieldSettings>,
+    spacing: Res<BlockSpacing>,
+    mut camera_transform: Query<(&Camera, &mut MainCamera, &mut Transform, &mut Projection)>,
+    mut cursor_ray: ResMut<CursorRay>,
+) {
+    let (camera, mut main_camera, mut transform, mut projection) = camera_transform.single_mut();
+    let dt = time.delta_seconds();
+    let decay = |time_constant: f32| {
+        if time_constant > 0.0 {
+            (-dt / time_constant).exp()
+        } else {
+            0.0
+        }
+    };
+    main_camera.velocity *= decay(game_settings.camera_inertia);
+    if main_camera.velocity.length() < 0.01 {
+        main_camera.velocity = Vec2::ZERO;
+    }
+    let goal = main_camera.goal;
+    main_camera
+        .current
+        .ease_towards(&goal, 1.0 - decay(game_settings.camera_smoothing));
+    let mut orbit = main_camera.current;
+    if let Projection::Orthographic(ortho) = &mut *projection {
+        // Zoom by scaling the view to what perspective would show at the same distance
+        let height = 2.0 * orbit.distance * (MainCamera::FOV / 2.0).tan();
+        if !matches!(ortho.scaling_mode, ScalingMode::FixedVertical(h) if h == height) {
+            ortho.scaling_mode = ScalingMode::FixedVertical(height);
+        }
+        let radius = field_radius(field_settings.field_size, spacing.current());
+        orbit.distance = orthographic_distance(orbit.target, radius);
+    }
+    transform.set_if_neq(orbit.transform());
+    // The camera or pointer may have moved
+    cursor_ray.ray = cursor_ray
+        .position
+        .and_then(|cursor_pos| get_cursor_ray(camera, &transform, cursor_pos));
 }
 
 fn get_cursor_ray(
@@ -125,8 +457,11 @@ fn get_cursor_ray(
         invert_cursor_pos - Vec2::new(viewport.min.x, screen_size.y - viewport.max.y);
 
     let projection = camera.projection_matrix();
-    let far_ndc = projection.project_point3(Vec3::NEG_Z).z;
-    let near_ndc = projection.project_point3(Vec3::Z).z;
+    // Both projections use reversed depth, with the near plane at 1. The far plane is at 0,
+    // which is infinitely far away for perspective, so use a point just in front of it.
+    // Unlike points projected from a fixed distance, these lie on the ray for either projection.
+    let near_ndc = 1.0;
+    let far_ndc = f32::EPSILON;
     let cursor_ndc = (adj_cursor_pos / viewport.size()) * 2.0 - Vec2::ONE;
     let ndc_to_world: Mat4 = view * projection.inverse();
     let near = ndc_to_world.project_point3(cursor_ndc.extend(near_ndc));
@@ -150,3 +485,37 @@ fn cursor_ray_gizmo(
     };
     gizmos.arrow(ray.origin, ray.get_point(20.0), Color::YELLOW);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orthographic_camera_stays_outside_field() {
+        let field_size = [5, 4, 3];
+        let spacing = BlockSpacing::default();
+        let radius = field_radius(field_size, spacing.current());
+        let corner = spacing.position([4, 3, 2], field_size);
+        // Focused on the opposite corner, so the field is mostly behind the target
+        let target = spacing.position([0, 0, 0], field_size);
+        // Looking from the far corner puts it furthest behind the target
+        for direction in [
+            corner - target,
+            Vec3::X,
+            Vec3::NEG_Z,
+            Vec3::new(1.0, 1.0, -1.0),
+        ] {
+            let mut orbit = Orbit {
+                target,
+                distance: orthographic_distance(target, radius),
+                ..default()
+            };
+            orbit.look_from(direction);
+            let transform = orbit.transform();
+            for offset in [Vec3::splat(-0.5), Vec3::splat(0.5)] {
+                let depth = (corner + offset - transform.translation).dot(*transform.forward());
+                assert!(depth > 0.0, "corner behind camera looking from {direction}");
+            }
+        }
+    }
+}
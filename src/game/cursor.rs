@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+use super::block::{chord_targets, Block, BlockEvent, HoveredBlock};
+use super::camera::MainCamera;
+use super::minefield::FieldEvent;
+use super::{GamePiece, GameState, GameStats};
+use crate::{FieldSettings, GameSettings, InputEvent};
+
+pub struct CursorPlugin;
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BlockCursor>();
+        app.add_systems(OnEnter(GameState::GameStart), spawn.after(super::cleanup));
+        app.add_systems(
+            Update,
+            handle_cursor_input
+                .after(super::camera::camera_controls)
+                .before(super::minefield::handle_field_events)
+                .run_if(GameState::playable()),
+        );
+        app.add_systems(Update, update_cursor_display.run_if(GameState::in_game()));
+    }
+}
+
+/// A block selected with the keyboard, for playing without a mouse.
+#[derive(Debug, Default, Resource)]
+pub struct BlockCursor {
+    /// Field index of the selected block, or `None` until the cursor is first moved.
+    pub index: Option<[usize; 3]>,
+}
+
+/// Marker component for the entity showing the [BlockCursor].
+#[derive(Component)]
+struct CursorDisplay;
+
+fn spawn(
+    mut commands: Commands,
+    mut cursor: ResMut<BlockCursor>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    *cursor = BlockCursor::default();
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cuboid::new(1.15, 1.15, 1.15)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgba(1.0, 1.0, 0.3, 0.25),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        CursorDisplay,
+        GamePiece,
+    ));
+}
+
+/// Return the field axis and direction most closely matching `direction`.
+fn nearest_axis(direction: Vec3) -> (usize, isize) {
+    let abs = direction.abs();
+    let axis = if abs.x >= abs.y && abs.x >= abs.z {
+        0
+    } else if abs.y >= abs.z {
+        1
+    } else {
+        2
+    };
+    (axis, if direction[axis] >= 0.0 { 1 } else { -1 })
+}
+
+/// What to do with a block selected by its field index.
+enum IndexAction {
+    Clear,
+    Mark,
+    Chord,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_cursor_input(
+    field_settings: Res<FieldSettings>,
+    game_settings: Res<GameSettings>,
+    hovered: Res<HoveredBlock>,
+    camera: Query<&Transform, With<MainCamera>>,
+    blocks: Query<(Entity, &Block)>,
+    mut input_events: EventReader<InputEvent>,
+    mut cursor: ResMut<BlockCursor>,
+    mut stats: ResMut<GameStats>,
+    mut field_events: EventWriter<FieldEvent>,
+    mut block_events: EventWriter<BlockEvent>,
+) {
+    let field_size = field_settings.field_size;
+    let block_at = |index| blocks.iter().find(|(_, block)| block.index() == index);
+    for input_event in input_events.read() {
+        let (selected, action) = match input_event {
+            InputEvent::MoveCursor { direction } => {
+                let Some(index) = cursor.index else {
+                    // Start from the hovered block, or the center of the field
+                    cursor.index = Some(
+                        hovered
+                            .target
+                            .map(|(_, index)| index)
+                            .unwrap_or(field_size.map(|size| size / 2)),
+                    );
+                    continue;
+                };
+                // Move along the field axis closest to the direction on screen
+                let world_direction = camera.single().rotation * *direction;
+                let (axis, step) = nearest_axis(world_direction);
+                let mut index = index;
+                index[axis] = index[axis]
+                    .saturating_add_signed(step)
+                    .min(field_size[axis] - 1);
+                debug!("Move cursor to {index:?}");
+                cursor.index = Some(index);
+                continue;
+            }
+            InputEvent::ClearSelected => (cursor.index, IndexAction::Clear),
+            InputEvent::ClearAt { index } => (Some(*index), IndexAction::Clear),
+            InputEvent::MarkSelected => (cursor.index, IndexAction::Mark),
+            InputEvent::MarkAt { index } => (Some(*index), IndexAction::Mark),
+            InputEvent::ChordSelected => (cursor.index, IndexAction::Chord),
+            InputEvent::ChordAt { index } => (Some(*index), IndexAction::Chord),
+            _ => continue,
+        };
+        let Some((entity, block)) = selected.and_then(block_at) else {
+            continue;
+        };
+        match action {
+            IndexAction::Clear => {
+                if block.is_hidden() && !block.is_protected(&game_settings) {
+                    stats.clicks += 1;
+                    debug!("Send FieldEvent::ClearBlock");
+                    field_events.send(FieldEvent::ClearBlock(block.index()));
+                }
+            }
+            IndexAction::Mark => {
+                if block.is_hidden() {
+                    stats.clicks += 1;
+                    debug!("Send BlockEvent::Mark");
+                    block_events.send(BlockEvent::Mark(entity));
+                }
+            }
+            IndexAction::Chord => {
+                let adjacent = blocks.iter().map(|(_, block)| block);
+                let Some(targets) = chord_targets(block, adjacent, &game_settings) else {
+                    continue;
+                };
+                stats.clicks += 1;
+                for index in targets {
+                    debug!("Send FieldEvent::ClearBlock");
+                    field_events.send(FieldEvent::ClearBlock(index));
+                }
+            }
+        }
+    }
+}
+
+/// Show the cursor around the selected block.
+fn update_cursor_display(
+    cursor: Res<BlockCursor>,
+    blocks: Query<(&Block, &Transform), Without<CursorDisplay>>,
+    mut display: Query<(&mut Transform, &mut Visibility), With<CursorDisplay>>,
+) {
+    let Ok((mut transform, mut visibility)) = display.get_single_mut() else {
+        return;
+    };
+    let selected = cursor
+        .index
+        .and_then(|index| blocks.iter().find(|(block, _)| block.index() == index));
+    match selected {
+        Some((_, block_transform)) => {
+            transform.translation = block_transform.translation;
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+        None => {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+}
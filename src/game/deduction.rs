@@ -0,0 +1,452 @@
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use super::block::{Block, BlockEvent};
+use super::minefield::{Contains, Minefield};
+use super::{GameResult, GameState};
+
+/// Most steps to take enumerating mine arrangements before giving up on an analysis.
+/// The analysis runs within a single frame, so this keeps it to a few milliseconds.
+const MAX_SEARCH_STEPS: usize = 200_000;
+
+pub struct DeductionPlugin;
+impl Plugin for DeductionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LossAnalysis>();
+        app.add_systems(OnEnter(GameState::GameStart), reset.after(super::cleanup));
+        app.add_systems(
+            Update,
+            analyze_loss
+                .after(super::block::handle_block_events)
+                .run_if(GameState::playable()),
+        );
+    }
+}
+
+/// What could have been deduced before the click that lost the game.
+#[derive(Debug, Default, Resource)]
+pub enum LossAnalysis {
+    /// The game has not been lost.
+    #[default]
+    None,
+    /// There were too many possible mine arrangements to check.
+    TooComplex,
+    Analyzed {
+        /// The mine that was cleared.
+        clicked: [usize; 3],
+        /// Chance that the clicked block was a mine, given what was revealed before the click.
+        mine_probability: f64,
+        /// Hidden blocks that were certainly safe before the click. If there were none,
+        /// the click was a forced guess.
+        safe: Vec<[usize; 3]>,
+    },
+}
+impl LossAnalysis {
+    /// Whether the block at `index` was certainly safe before the losing click.
+    pub fn is_safe(&self, index: [usize; 3]) -> bool {
+        matches!(self, Self::Analyzed { safe, .. } if safe.contains(&index))
+    }
+}
+
+fn reset(mut analysis: ResMut<LossAnalysis>) {
+    *analysis = LossAnalysis::None;
+}
+
+/// When a mine ends the game, analyze the field as it was just before the click.
+fn analyze_loss(
+    game_result: Res<GameResult>,
+    field: Query<&Minefield>,
+    blocks: Query<&Block>,
+    mut block_events: EventReader<BlockEvent>,
+    mut analysis: ResMut<LossAnalysis>,
+) {
+    // Read the events every frame, so only those from the losing click are left to check
+    let cleared: Vec<_> = block_events
+        .read()
+        .filter_map(|event| match event {
+            BlockEvent::Clear(entity, contains, _) => Some((*entity, *contains)),
+            _ => None,
+        })
+        .filter_map(|(entity, contains)| Some((blocks.get(entity).ok()?.index(), contains)))
+        .collect();
+    if !game_result.is_changed() || !matches!(*game_result, GameResult::Failure) {
+        return;
+    }
+    let Some(clicked) = cleared
+        .iter()
+        .find(|(_, contains)| matches!(contains, Contains::Mine))
+        .map(|(index, _)| *index)
+    else {
+        warn!("Game lost without revealing a mine");
+        return;
+    };
+    // Everything cleared by the losing click was unknown before it
+    let just_revealed: HashSet<_> = cleared.iter().map(|(index, _)| *index).collect();
+    let revealed: HashMap<_, _> = blocks
+        .iter()
+        .filter(|block| !just_revealed.contains(&block.index()))
+        .filter_map(|block| block.revealed().map(|contains| (block.index(), contains)))
+        .collect();
+    let hidden: Vec<_> = blocks
+        .iter()
+        .map(|block| block.index())
+        .filter(|index| !revealed.contains_key(index))
+        .collect();
+    *analysis = match mine_probabilities(field.single(), &revealed, &hidden) {
+        Some(probabilities) => {
+            let mut safe: Vec<_> = probabilities
+                .iter()
+                .filter(|(_, probability)| **probability == 0.0)
+                .map(|(index, _)| *index)
+                .collect();
+            safe.sort_unstable();
+            info!("Losing click had {} safe alternatives", safe.len());
+            LossAnalysis::Analyzed {
+                clicked,
+                mine_probability: probabilities[&clicked],
+                safe,
+            }
+        }
+        None => LossAnalysis::TooComplex,
+    };
+}
+
+/// A revealed number, requiring some of its hidden neighbors to be mines.
+struct Constraint {
+    /// Frontier cells adjacent to the number.
+    cells: Vec<usize>,
+    /// Number of those cells that are mines.
+    required: usize,
+}
+
+/// Mine arrangements for a group of frontier cells linked by shared constraints.
+struct Component {
+    /// Frontier cells in the group.
+    cells: Vec<usize>,
+    /// Relative weight of the arrangements with each number of mines.
+    weights: Vec<f64>,
+    /// For each cell, relative weight of the arrangements with each number of mines
+    /// where that cell is a mine.
+    cell_weights: Vec<Vec<f64>>,
+}
+
+/// Backtracking search through the mine arrangements that satisfy every constraint.
+struct Search<'a> {
+    constraints: &'a [Constraint],
+    cell_constraints: &'a [Vec<usize>],
+    /// Mines placed so far in each constraint.
+    mines: Vec<usize>,
+    /// Cells not yet decided in each constraint.
+    undecided: Vec<usize>,
+    steps: usize,
+}
+impl Search<'_> {
+    fn enumerate(&mut self, cells: Vec<usize>) -> Option<Component> {
+        let mut component = Component {
+            weights: vec![0.0; cells.len() + 1],
+            cell_weights: vec![vec![0.0; cells.len() + 1]; cells.len()],
+            cells,
+        };
+        let mut assignment = vec![false; component.cells.len()];
+        self.search(&mut component, &mut assignment, 0, 0)?;
+        let total: f64 = component.weights.iter().sum();
+        if total <= 0.0 {
+            warn!("Revealed numbers cannot all be satisfied");
+            return None;
+        }
+        // Only the relative weights matter, and this keeps products between
+        // components from overflowing
+        component.weights.iter_mut().for_each(|w| *w /= total);
+        component
+            .cell_weights
+            .iter_mut()
+            .flatten()
+            .for_each(|w| *w /= total);
+        Some(component)
+    }
+    fn search(
+        &mut self,
+        component: &mut Component,
+        assignment: &mut [bool],
+        depth: usize,
+        placed: usize,
+    ) -> Option<()> {
+        self.steps += 1;
+        if self.steps > MAX_SEARCH_STEPS {
+            return None;
+        }
+        if depth == component.cells.len() {
+            self.steps += depth;
+            component.weights[placed] += 1.0;
+            for (cell_weights, _) in component
+                .cell_weights
+                .iter_mut()
+                .zip(assignment.iter())
+                .filter(|(_, mine)| **mine)
+            {
+                cell_weights[placed] += 1.0;
+            }
+            return Some(());
+        }
+        let cell_constraints = self.cell_constraints;
+        let cell_constraints = &cell_constraints[component.cells[depth]];
+        for mine in [false, true] {
+            let placing = mine as usize;
+            let possible = cell_constraints.iter().all(|&c| {
+                let mines = self.mines[c] + placing;
+                let undecided = self.undecided[c] - 1;
+                mines <= self.constraints[c].required
+                    && mines + undecided >= self.constraints[c].required
+            });
+            if !possible {
+                continue;
+            }
+            for &c in cell_constraints {
+                self.mines[c] += placing;
+                self.undecided[c] -= 1;
+            }
+            assignment[depth] = mine;
+            let result = self.search(component, assignment, depth + 1, placed + placing);
+            for &c in cell_constraints {
+                self.mines[c] -= placing;
+                self.undecided[c] += 1;
+            }
+            result?;
+        }
+        Some(())
+    }
+}
+
+/// Work out the chance that each hidden block is a mine, given the revealed blocks and
+/// the total number of mines. Returns `None` if there are too many arrangements to check.
+pub(super) fn mine_probabilities(
+    field: &Minefield,
+    revealed: &HashMap<[usize; 3], Contains>,
+    hidden: &[[usize; 3]],
+) -> Option<HashMap<[usize; 3], f64>> {
+    let known_mines = revealed
+        .values()
+        .filter(|contains| matches!(contains, Contains::Mine))
+        .count();
+    let remaining = field.num_mines().checked_sub(known_mines)?;
+
+    // Hidden cells next to a revealed number make up the frontier
+    let mut frontier = vec![];
+    let mut frontier_ids = HashMap::new();
+    let mut constraints = vec![];
+    for (&index, contains) in revealed {
+        let Contains::Empty { adjacent_mines } = *contains else {
+            continue;
+        };
+        let adjacent = field.adjacent(index);
+        let known = adjacent
+            .iter()
+            .filter(|adj| matches!(revealed.get(*adj), Some(Contains::Mine)))
+            .count();
+        let cells: Vec<usize> = adjacent
+            .into_iter()
+            .filter(|adj| !revealed.contains_key(adj))
+            .map(|adj| {
+                *frontier_ids.entry(adj).or_insert_with(|| {
+                    frontier.push(adj);
+                    frontier.len() - 1
+                })
+            })
+            .collect();
+        if !cells.is_empty() {
+            let required = (adjacent_mines as usize).checked_sub(known)?;
+            constraints.push(Constraint { cells, required });
+        }
+    }
+    let mut cell_constraints = vec![vec![]; frontier.len()];
+    for (c, constraint) in constraints.iter().enumerate() {
+        for &cell in &constraint.cells {
+            cell_constraints[cell].push(c);
+        }
+    }
+
+    // Cells only affect each other through shared constraints, so each connected
+    // group can be enumerated separately
+    let mut search = Search {
+        constraints: &constraints,
+        cell_constraints: &cell_constraints,
+        mines: vec![0; constraints.len()],
+        undecided: constraints.iter().map(|c| c.cells.len()).collect(),
+        steps: 0,
+    };
+    let mut components = vec![];
+    let mut visited = vec![false; frontier.len()];
+    for start in 0..frontier.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        // Breadth-first order keeps linked cells together, so the search fails early
+        let mut cells = vec![start];
+        let mut next = 0;
+        while next < cells.len() {
+            for &c in &cell_constraints[cells[next]] {
+                for &cell in &constraints[c].cells {
+                    if !visited[cell] {
+                        visited[cell] = true;
+                        cells.push(cell);
+                    }
+                }
+            }
+            next += 1;
+        }
+        components.push(search.enumerate(cells)?);
+    }
+
+    // Weight each number of frontier mines by the ways to place the rest elsewhere,
+    // using logarithms since the counts are far too large for floats
+    let interior = hidden.len() - frontier.len();
+    let ln_factorial: Vec<f64> = std::iter::once(0.0)
+        .chain((1..=hidden.len()).scan(0.0, |acc, n| {
+            *acc += (n as f64).ln();
+            Some(*acc)
+        }))
+        .collect();
+    let ln_weights: Vec<Option<f64>> = (0..=frontier.len())
+        .map(|mines| {
+            let rest = remaining.checked_sub(mines)?;
+            (rest <= interior).then(|| {
+                ln_factorial[interior] - ln_factorial[rest] - ln_factorial[interior - rest]
+            })
+        })
+        .collect();
+    let max_ln_weight = ln_weights
+        .iter()
+        .flatten()
+        .copied()
+        .fold(f64::MIN, f64::max);
+    let interior_weights: Vec<f64> = ln_weights
+        .iter()
+        .map(|w| w.map_or(0.0, |w| (w - max_ln_weight).exp()))
+        .collect();
+
+    // Combine the components, leaving each one out in turn
+    let convolve = |a: &[f64], b: &[f64]| {
+        let mut out = vec![0.0; a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                out[i + j] += x * y;
+            }
+        }
+        out
+    };
+    let mut prefix = vec![vec![1.0]];
+    for component in &components {
+        prefix.push(convolve(prefix.last().unwrap(), &component.weights));
+    }
+    let mut suffix = vec![vec![1.0]];
+    for component in components.iter().rev() {
+        suffix.push(convolve(suffix.last().unwrap(), &component.weights));
+    }
+    suffix.reverse();
+    let all = prefix.last().unwrap();
+    let total: f64 = all
+        .iter()
+        .zip(&interior_weights)
+        .map(|(w, interior_weight)| w * interior_weight)
+        .sum();
+    if total <= 0.0 {
+        warn!("No arrangement of the remaining mines fits the revealed numbers");
+        return None;
+    }
+
+    let mut probabilities = HashMap::new();
+    for (n, component) in components.iter().enumerate() {
+        let others = convolve(&prefix[n], &suffix[n + 1]);
+        // Weight of this component having each number of mines, given everything else
+        let context: Vec<f64> = (0..component.weights.len())
+            .map(|mines| {
+                others
+                    .iter()
+                    .enumerate()
+                    .map(|(other_mines, w)| w * interior_weights[mines + other_mines])
+                    .sum()
+            })
+            .collect();
+        for (&cell, cell_weights) in component.cells.iter().zip(&component.cell_weights) {
+            let weight: f64 = cell_weights.iter().zip(&context).map(|(w, c)| w * c).sum();
+            probabilities.insert(frontier[cell], weight / total);
+        }
+    }
+    if interior > 0 {
+        let weight: f64 = all
+            .iter()
+            .zip(&interior_weights)
+            .enumerate()
+            .map(|(mines, (w, interior_weight))| {
+                w * interior_weight * remaining.saturating_sub(mines) as f64
+            })
+            .sum();
+        let probability = weight / interior as f64 / total;
+        for index in hidden
+            .iter()
+            .filter(|index| !frontier_ids.contains_key(*index))
+        {
+            probabilities.insert(*index, probability);
+        }
+    }
+    Some(probabilities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Find the mine probabilities in a field of `field_size` with only the blocks at
+    /// `revealed` revealed.
+    fn probabilities(
+        field: &Minefield,
+        field_size: [usize; 3],
+        revealed: &[[usize; 3]],
+    ) -> HashMap<[usize; 3], f64> {
+        let revealed: HashMap<_, _> = revealed
+            .iter()
+            .map(|index| (*index, field.contains(*index).unwrap()))
+            .collect();
+        let hidden: Vec<_> = (0..field_size[0])
+            .flat_map(|i| {
+                (0..field_size[1]).flat_map(move |j| (0..field_size[2]).map(move |k| [i, j, k]))
+            })
+            .filter(|index| !revealed.contains_key(index))
+            .collect();
+        mine_probabilities(field, &revealed, &hidden).unwrap()
+    }
+
+    #[test]
+    fn forced_guess_is_even() {
+        // A 1 between two hidden blocks, with one mine in the field
+        let field_size = [3, 1, 1];
+        let field = Minefield::with_mines(field_size, &[[0, 0, 0]]);
+        let probabilities = probabilities(&field, field_size, &[[1, 0, 0]]);
+        assert_eq!(probabilities.len(), 2);
+        assert_eq!(probabilities[&[0, 0, 0]], 0.5);
+        assert_eq!(probabilities[&[2, 0, 0]], 0.5);
+    }
+
+    #[test]
+    fn neighbor_of_zero_is_safe() {
+        let field_size = [3, 1, 1];
+        let field = Minefield::with_mines(field_size, &[[0, 0, 0]]);
+        let probabilities = probabilities(&field, field_size, &[[2, 0, 0]]);
+        assert_eq!(probabilities[&[1, 0, 0]], 0.0);
+        // The only mine left has nowhere else to go
+        assert_eq!(probabilities[&[0, 0, 0]], 1.0);
+    }
+
+    #[test]
+    fn interior_shares_remaining_mines() {
+        // The 0 makes its neighbor safe, leaving the mine somewhere in the other three
+        let field_size = [5, 1, 1];
+        let field = Minefield::with_mines(field_size, &[[3, 0, 0]]);
+        let probabilities = probabilities(&field, field_size, &[[0, 0, 0]]);
+        assert_eq!(probabilities[&[1, 0, 0]], 0.0);
+        for i in 2..5 {
+            assert!((probabilities[&[i, 0, 0]] - 1.0 / 3.0).abs() < 1e-9);
+        }
+    }
+}
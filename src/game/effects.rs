@@ -0,0 +1,337 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use bevy::utils::HashMap;
+
+use super::block::{Block, BlockEvent, BlockSpacing, RevealAnimation};
+use super::minefield::{Contains, Minefield};
+use super::{GamePiece, GameResult, GameState};
+use crate::{FieldSettings, GameSettings, InputEvent};
+
+/// Acceleration of falling fragments.
+const GRAVITY: Vec3 = Vec3::new(0.0, -9.8, 0.0);
+const FRAGMENTS_PER_MINE: usize = 24;
+/// Seconds that fragments last, shrinking away at the end.
+const FRAGMENT_SECS: f32 = 1.5;
+const FRAGMENT_SHRINK_SECS: f32 = 0.3;
+/// Distance from an exploding mine, in blocks, within which blocks are knocked outward.
+const KNOCKBACK_RADIUS: f32 = 2.5;
+/// Speed given to blocks right next to an exploding mine.
+const KNOCKBACK_SPEED: f32 = 4.0;
+/// Stiffness and damping of the spring pulling knocked blocks back into place.
+const KNOCKBACK_STIFFNESS: f32 = 80.0;
+const KNOCKBACK_DAMPING: f32 = 8.0;
+/// Seconds between each mine lighting up after a victory.
+const GLOW_INTERVAL_SECS: f32 = 0.08;
+/// Seconds that each mine pulses for as it lights up.
+const GLOW_PULSE_SECS: f32 = 0.3;
+
+/// Mine explosions and victory effects, which only change how the field looks.
+pub struct EffectsPlugin;
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, create_assets);
+        app.add_systems(OnEnter(GameState::GameOver), light_up_mines);
+        app.add_systems(
+            Update,
+            (
+                spawn_explosions,
+                animate_fragments,
+                animate_knockback,
+                animate_glow,
+                skip_effects,
+            )
+                .chain()
+                .after(super::block::handle_block_events)
+                .run_if(GameState::in_game()),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct EffectAssets {
+    fragment: Handle<Mesh>,
+    debris: Handle<StandardMaterial>,
+    fire: Handle<StandardMaterial>,
+    /// Mines lit up after a victory.
+    glow: Handle<StandardMaterial>,
+}
+
+/// A piece of a shattered block, flying away from an explosion.
+#[derive(Component)]
+struct Fragment {
+    velocity: Vec3,
+    /// Axis and speed of rotation, in radians per second.
+    spin: Vec3,
+    size: f32,
+    /// Seconds until the fragment disappears.
+    remaining: f32,
+}
+
+/// Displacement of a block knocked outward by an explosion, springing back into place.
+#[derive(Component, Default)]
+struct Knockback {
+    offset: Vec3,
+    velocity: Vec3,
+}
+
+/// A mine waiting to light up, or pulsing as it does, after a victory.
+#[derive(Component)]
+struct MineGlow {
+    /// Seconds since the mine lit up, negative until it does.
+    elapsed: f32,
+}
+
+fn create_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(EffectAssets {
+        fragment: meshes.add(Cuboid::new(0.2, 0.2, 0.2)),
+        debris: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.5, 0.5, 0.45),
+            perceptual_roughness: 1.0,
+            ..default()
+        }),
+        fire: materials.add(StandardMaterial {
+            base_color: Color::ORANGE_RED,
+            emissive: Color::rgb(1.0, 0.4, 0.0),
+            ..default()
+        }),
+        glow: materials.add(StandardMaterial {
+            base_color: Color::GOLD,
+            emissive: Color::rgb(1.0, 0.8, 0.2),
+            ..default()
+        }),
+    });
+}
+
+/// Shatter blocks into fragments when a mine is cleared, knocking nearby blocks outward.
+fn spawn_explosions(
+    game_settings: Res<GameSettings>,
+    field_settings: Res<FieldSettings>,
+    spacing: Res<BlockSpacing>,
+    assets: Res<EffectAssets>,
+    mut commands: Commands,
+    mut block_events: EventReader<BlockEvent>,
+    mut blocks: Query<(Entity, &Block, Option<&mut Knockback>)>,
+) {
+    let mut rng = thread_rng();
+    // Several mines can go off at once, so add up their impulses before applying them
+    let mut impulses: HashMap<Entity, Vec3> = HashMap::new();
+    for event in block_events.read() {
+        let BlockEvent::Clear(entity, Contains::Mine, _) = event else {
+            continue;
+        };
+        if game_settings.reduced_motion {
+            continue;
+        }
+        let Ok((_, block, _)) = blocks.get(*entity) else {
+            continue;
+        };
+        let center = spacing.position(block.index(), field_settings.field_size);
+        for _ in 0..FRAGMENTS_PER_MINE {
+            let direction = random_direction(&mut rng);
+            let size = rng.gen_range(0.5..1.5);
+            let material = if rng.gen_bool(0.3) {
+                assets.fire.clone()
+            } else {
+                assets.debris.clone()
+            };
+            commands.spawn((
+                PbrBundle {
+                    mesh: assets.fragment.clone(),
+                    material,
+                    transform: Transform::from_translation(center + direction * 0.3)
+                        .with_rotation(Quat::from_scaled_axis(random_direction(&mut rng) * PI))
+                        .with_scale(Vec3::splat(size)),
+                    ..default()
+                },
+                Fragment {
+                    velocity: direction * rng.gen_range(2.0..6.0) + Vec3::Y * 2.0,
+                    spin: random_direction(&mut rng) * rng.gen_range(2.0..10.0),
+                    size,
+                    remaining: FRAGMENT_SECS * rng.gen_range(0.7..1.0),
+                },
+                GamePiece,
+            ));
+        }
+        for (other, block, _) in &blocks {
+            if other == *entity {
+                continue;
+            }
+            // Measure in blocks, so spreading the field apart doesn't change the effect
+            let away = (spacing.position(block.index(), field_settings.field_size) - center)
+                / spacing.current();
+            let distance = away.length();
+            if distance > KNOCKBACK_RADIUS {
+                continue;
+            }
+            let impulse =
+                away.normalize_or_zero() * KNOCKBACK_SPEED * (1.0 - distance / KNOCKBACK_RADIUS);
+            *impulses.entry(other).or_default() += impulse;
+        }
+    }
+    for (entity, impulse) in impulses {
+        match blocks.get_mut(entity) {
+            Ok((_, _, Some(mut knockback))) => knockback.velocity += impulse,
+            _ => {
+                commands.entity(entity).insert(Knockback {
+                    velocity: impulse,
+                    ..default()
+                });
+            }
+        }
+    }
+}
+
+fn random_direction(rng: &mut impl Rng) -> Vec3 {
+    Vec3::new(
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(-1.0..1.0),
+    )
+    .try_normalize()
+    .unwrap_or(Vec3::Y)
+}
+
+/// Move fragments under gravity, removing them once they have shrunk away.
+fn animate_fragments(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut fragments: Query<(Entity, &mut Transform, &mut Fragment)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut transform, mut fragment) in &mut fragments {
+        fragment.remaining -= delta;
+        if fragment.remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        fragment.velocity += GRAVITY * delta;
+        transform.translation += fragment.velocity * delta;
+        transform.rotate(Quat::from_scaled_axis(fragment.spin * delta));
+        let shrink = (fragment.remaining / FRAGMENT_SHRINK_SECS).min(1.0);
+        transform.scale = Vec3::splat(fragment.size * shrink);
+    }
+}
+
+/// Spring knocked blocks back into place.
+fn animate_knockback(
+    time: Res<Time>,
+    field_settings: Res<FieldSettings>,
+    spacing: Res<BlockSpacing>,
+    mut commands: Commands,
+    mut blocks: Query<(Entity, &Block, &mut Transform, &mut Knockback)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, block, mut transform, mut knockback) in &mut blocks {
+        let acceleration =
+            -KNOCKBACK_STIFFNESS * knockback.offset - KNOCKBACK_DAMPING * knockback.velocity;
+        knockback.velocity += acceleration * delta;
+        let velocity = knockback.velocity;
+        knockback.offset += velocity * delta;
+        let position = spacing.position(block.index(), field_settings.field_size);
+        if knockback.offset.length() < 0.001 && knockback.velocity.length() < 0.01 {
+            transform.translation = position;
+            commands.entity(entity).remove::<Knockback>();
+        } else {
+            transform.translation = position + knockback.offset;
+        }
+    }
+}
+
+/// After a victory, light up the mines one after another, spreading out from the center.
+fn light_up_mines(
+    game_result: Res<GameResult>,
+    game_settings: Res<GameSettings>,
+    field: Query<&Minefield>,
+    blocks: Query<(Entity, &Block, &Transform)>,
+    mut commands: Commands,
+) {
+    if !matches!(*game_result, GameResult::Victory) || game_settings.reduced_motion {
+        return;
+    }
+    let field = field.single();
+    let mut mines: Vec<_> = blocks
+        .iter()
+        .filter(|(_, block, _)| matches!(field.contains(block.index()), Some(Contains::Mine)))
+        .map(|(entity, _, transform)| (entity, transform.translation.length()))
+        .collect();
+    mines.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    for (n, (entity, _)) in mines.into_iter().enumerate() {
+        // Wait at least a frame, so the mine is displayed before it lights up
+        commands.entity(entity).insert(MineGlow {
+            elapsed: -((n + 1) as f32) * GLOW_INTERVAL_SECS,
+        });
+    }
+}
+
+/// Light up and pulse each mine when its turn comes.
+fn animate_glow(
+    time: Res<Time>,
+    assets: Res<EffectAssets>,
+    mut commands: Commands,
+    mut mines: Query<(Entity, &mut Transform, &mut MineGlow), Without<Knockback>>,
+) {
+    for (entity, mut transform, mut glow) in &mut mines {
+        let lit = glow.elapsed >= 0.0;
+        glow.elapsed += time.delta_seconds();
+        if glow.elapsed < 0.0 {
+            continue;
+        }
+        if !lit {
+            commands.entity(entity).insert(assets.glow.clone());
+        }
+        let progress = glow.elapsed / GLOW_PULSE_SECS;
+        if progress < 1.0 {
+            transform.scale = Vec3::splat(1.0 + 0.4 * (progress * PI).sin());
+        } else {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<MineGlow>();
+        }
+    }
+}
+
+/// Finish all effects, including reveal waves, immediately when asked to skip them.
+#[allow(clippy::too_many_arguments)]
+fn skip_effects(
+    field_settings: Res<FieldSettings>,
+    spacing: Res<BlockSpacing>,
+    assets: Res<EffectAssets>,
+    mut commands: Commands,
+    mut input_events: EventReader<InputEvent>,
+    fragments: Query<Entity, With<Fragment>>,
+    mut knocked: Query<(Entity, &Block, &mut Transform), With<Knockback>>,
+    mut glowing: Query<(Entity, &mut Transform, &MineGlow), Without<Knockback>>,
+    mut reveals: Query<&mut RevealAnimation>,
+) {
+    let skip = input_events
+        .read()
+        .filter(|event| matches!(event, InputEvent::SkipEffects))
+        .count();
+    if skip == 0 {
+        return;
+    }
+    debug!("Skip effects");
+    for entity in &fragments {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (entity, block, mut transform) in &mut knocked {
+        transform.translation = spacing.position(block.index(), field_settings.field_size);
+        commands.entity(entity).remove::<Knockback>();
+    }
+    for (entity, mut transform, _) in &mut glowing {
+        transform.scale = Vec3::ONE;
+        commands
+            .entity(entity)
+            .insert(assets.glow.clone())
+            .remove::<MineGlow>();
+    }
+    for mut animation in &mut reveals {
+        animation.finish();
+    }
+}
@@ -0,0 +1,330 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use super::block::{Block, BlockMaterials, HoveredBlock, Marking};
+use super::deduction::LossAnalysis;
+use super::minefield::{Contains, Minefield};
+use super::training::Mistakes;
+use crate::{GameSettings, GameState};
+
+pub struct HighlightPlugin;
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Focus>();
+        app.init_resource::<Frontier>();
+        app.add_systems(
+            Startup,
+            create_materials.after(super::block::create_materials),
+        );
+        app.add_systems(
+            Update,
+            (update_focus, update_frontier, apply_highlights)
+                .chain()
+                .after(super::block::update_hover)
+                .run_if(GameState::in_game()),
+        );
+    }
+}
+
+/// A revealed number whose neighborhood is emphasized, fading out all other blocks.
+#[derive(Debug, Default, Resource, PartialEq)]
+pub struct Focus {
+    pub center: Option<[usize; 3]>,
+}
+
+/// Hidden, unmarked blocks adjacent to at least one revealed number, which are the only
+/// places where logic can make progress.
+#[derive(Debug, Default, Resource)]
+pub struct Frontier {
+    /// Field indices of frontier blocks, in order.
+    blocks: Vec<[usize; 3]>,
+    /// The frontier block most recently cycled to.
+    last: Option<[usize; 3]>,
+}
+impl Frontier {
+    pub fn contains(&self, index: [usize; 3]) -> bool {
+        self.blocks.binary_search(&index).is_ok()
+    }
+    /// Get the next frontier block after the last one cycled to, wrapping around.
+    pub fn cycle(&mut self) -> Option<[usize; 3]> {
+        let next = self
+            .last
+            .and_then(|last| self.blocks.iter().find(|index| **index > last))
+            .or(self.blocks.first())
+            .copied();
+        self.last = next;
+        next
+    }
+}
+
+/// Remembers the material of an entity whose material has been overridden.
+#[derive(Component)]
+struct MaterialOverride {
+    original: Handle<StandardMaterial>,
+}
+
+#[derive(Resource)]
+struct HighlightMaterials {
+    /// Blocks outside the focused neighborhood.
+    faded: Handle<StandardMaterial>,
+    /// Hidden, unmarked blocks inside the focused neighborhood.
+    highlighted: Handle<StandardMaterial>,
+    /// Marked blocks inside the focused neighborhood.
+    highlighted_marked: Handle<StandardMaterial>,
+    /// Maybe-marked blocks inside the focused neighborhood.
+    highlighted_maybe: Handle<StandardMaterial>,
+    /// The hidden block that would be cleared or marked by a click.
+    hovered: Handle<StandardMaterial>,
+    /// The marked block that would be unmarked by a click.
+    hovered_marked: Handle<StandardMaterial>,
+    /// The maybe-marked block under the pointer.
+    hovered_maybe: Handle<StandardMaterial>,
+    /// Blocks in the [Frontier], if highlighted.
+    frontier: Handle<StandardMaterial>,
+    /// Blocks flagged as [Mistakes] in training mode.
+    mistake: Handle<StandardMaterial>,
+    /// Blocks that were certainly safe when the game was lost.
+    safe: Handle<StandardMaterial>,
+}
+impl HighlightMaterials {
+    fn is_override(&self, material: &Handle<StandardMaterial>) -> bool {
+        [
+            &self.faded,
+            &self.highlighted,
+            &self.highlighted_marked,
+            &self.highlighted_maybe,
+            &self.hovered,
+            &self.hovered_marked,
+            &self.hovered_maybe,
+            &self.frontier,
+            &self.mistake,
+            &self.safe,
+        ]
+        .contains(&material)
+    }
+}
+
+fn create_materials(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    block_mat: Res<BlockMaterials>,
+) {
+    // Hovered and highlighted marked blocks keep their own look, and just light up
+    let mut light_up = |marking, emissive| {
+        let mut material = materials.get(block_mat.hidden(marking)).unwrap().clone();
+        material.emissive = emissive;
+        materials.add(material)
+    };
+    let highlighted_marked = light_up(Marking::Mine, Color::rgb(0.1, 0.2, 0.4));
+    let highlighted_maybe = light_up(Marking::Maybe, Color::rgb(0.1, 0.2, 0.4));
+    let hovered = light_up(Marking::None, Color::rgb(0.3, 0.3, 0.2));
+    let hovered_marked = light_up(Marking::Mine, Color::rgb(0.4, 0.1, 0.1));
+    let hovered_maybe = light_up(Marking::Maybe, Color::rgb(0.4, 0.4, 0.1));
+    commands.insert_resource(HighlightMaterials {
+        faded: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.5, 0.5, 0.5, 0.1),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        highlighted: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.6, 0.8, 1.0),
+            emissive: Color::rgb(0.1, 0.2, 0.4),
+            ..default()
+        }),
+        highlighted_marked,
+        highlighted_maybe,
+        hovered,
+        hovered_marked,
+        hovered_maybe,
+        frontier: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.8, 0.5),
+            emissive: Color::rgb(0.3, 0.15, 0.0),
+            ..default()
+        }),
+        mistake: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.4, 0.1),
+            emissive: Color::rgb(0.6, 0.15, 0.0),
+            ..default()
+        }),
+        safe: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.4, 1.0, 0.5),
+            emissive: Color::rgb(0.05, 0.4, 0.1),
+            ..default()
+        }),
+    });
+}
+
+/// Focus on the revealed number under the pointer, if enabled.
+fn update_focus(
+    game_settings: Res<GameSettings>,
+    hovered: Res<HoveredBlock>,
+    blocks: Query<&Block>,
+    mut focus: ResMut<Focus>,
+) {
+    let center = hovered
+        .block
+        .filter(|_| game_settings.focus_on_hover)
+        .filter(|(entity, _)| {
+            blocks.get(*entity).is_ok_and(|block| {
+                matches!(block.revealed(), Some(Contains::Empty { adjacent_mines }) if adjacent_mines > 0)
+            })
+        })
+        .map(|(_, index)| index);
+    focus.set_if_neq(Focus { center });
+}
+
+/// Find the frontier whenever blocks are revealed or marked.
+fn update_frontier(
+    field: Query<&Minefield>,
+    blocks: Query<&Block>,
+    changed_blocks: Query<(), Changed<Block>>,
+    mut frontier: ResMut<Frontier>,
+) {
+    if changed_blocks.is_empty() {
+        return;
+    }
+    let Ok(field) = field.get_single() else {
+        return;
+    };
+    let hidden: HashSet<_> = blocks
+        .iter()
+        .filter(|block| block.is_hidden() && block.marking() == Marking::None)
+        .map(|block| block.index())
+        .collect();
+    let mut frontier_blocks = vec![];
+    let numbers = blocks.iter().filter(|block| {
+        matches!(block.revealed(), Some(Contains::Empty { adjacent_mines }) if adjacent_mines > 0)
+    });
+    for center in numbers.map(|block| block.index()) {
+        let adjacent = field.adjacent(center);
+        frontier_blocks.extend(adjacent.into_iter().filter(|index| hidden.contains(index)));
+    }
+    frontier_blocks.sort_unstable();
+    frontier_blocks.dedup();
+    if frontier.blocks != frontier_blocks {
+        frontier.blocks = frontier_blocks;
+    }
+}
+
+/// Override block materials to show the hovered block, training mistakes, safe blocks
+/// missed by the losing click, the focused neighborhood and the frontier.
+#[allow(clippy::too_many_arguments)]
+fn apply_highlights(
+    game_settings: Res<GameSettings>,
+    focus: Res<Focus>,
+    frontier: Res<Frontier>,
+    mistakes: Res<Mistakes>,
+    loss_analysis: Res<LossAnalysis>,
+    hovered: Res<HoveredBlock>,
+    highlight_mat: Res<HighlightMaterials>,
+    blocks: Query<(Entity, &Block, Option<&Children>)>,
+    changed_materials: Query<(), Changed<Handle<StandardMaterial>>>,
+    mut materials: Query<(&mut Handle<StandardMaterial>, Option<&mut MaterialOverride>)>,
+    mut commands: Commands,
+) {
+    // Block displays may have been replaced, so check them again as well
+    if !focus.is_changed()
+        && !hovered.is_changed()
+        && !frontier.is_changed()
+        && !mistakes.is_changed()
+        && !loss_analysis.is_changed()
+        && !game_settings.is_changed()
+        && changed_materials.is_empty()
+    {
+        return;
+    }
+    let hovered_target = hovered.target.map(|(entity, _)| entity);
+    for (entity, block, children) in &blocks {
+        if hovered_target == Some(entity) {
+            let desired = match block.marking() {
+                Marking::Mine => &highlight_mat.hovered_marked,
+                Marking::Maybe => &highlight_mat.hovered_maybe,
+                Marking::None => &highlight_mat.hovered,
+            };
+            override_material(
+                entity,
+                Some(desired),
+                &highlight_mat,
+                &mut materials,
+                &mut commands,
+            );
+            continue;
+        }
+        let mistake = mistakes
+            .contains(block.index())
+            .then_some(&highlight_mat.mistake);
+        let safe = loss_analysis
+            .is_safe(block.index())
+            .then_some(&highlight_mat.safe);
+        let desired = mistake.or(safe).or_else(|| {
+            focus.center.and_then(|center| {
+                let index = block.index();
+                let offset = [0, 1, 2].map(|n| index[n] as isize - center[n] as isize);
+                if index == center {
+                    None
+                } else if game_settings.neighborhood.contains(offset) {
+                    // Hidden and marked neighbors are highlighted, keeping their marking
+                    block.revealed().is_none().then(|| match block.marking() {
+                        Marking::None => &highlight_mat.highlighted,
+                        Marking::Mine => &highlight_mat.highlighted_marked,
+                        Marking::Maybe => &highlight_mat.highlighted_maybe,
+                    })
+                } else {
+                    Some(&highlight_mat.faded)
+                }
+            })
+        });
+        let desired = desired.or_else(|| {
+            (game_settings.highlight_frontier && frontier.contains(block.index()))
+                .then_some(&highlight_mat.frontier)
+        });
+        let entities = std::iter::once(entity).chain(children.into_iter().flatten().copied());
+        for entity in entities {
+            override_material(
+                entity,
+                desired,
+                &highlight_mat,
+                &mut materials,
+                &mut commands,
+            );
+        }
+    }
+}
+
+/// Replace the material of `entity` with `desired`, or restore its original material if `None`.
+fn override_material(
+    entity: Entity,
+    desired: Option<&Handle<StandardMaterial>>,
+    highlight_mat: &HighlightMaterials,
+    materials: &mut Query<(&mut Handle<StandardMaterial>, Option<&mut MaterialOverride>)>,
+    commands: &mut Commands,
+) {
+    let Ok((mut material, material_override)) = materials.get_mut(entity) else {
+        return;
+    };
+    match (desired, material_override) {
+        (None, None) => {}
+        (None, Some(material_override)) => {
+            if highlight_mat.is_override(&material) {
+                *material = material_override.original.clone();
+            }
+            commands.entity(entity).remove::<MaterialOverride>();
+        }
+        (Some(desired), Some(mut material_override)) => {
+            if *material != *desired {
+                // The block display changed since it was overridden
+                if !highlight_mat.is_override(&material) {
+                    material_override.original = material.clone();
+                }
+                *material = desired.clone();
+            }
+        }
+        (Some(desired), None) => {
+            commands.entity(entity).insert(MaterialOverride {
+                original: material.clone(),
+            });
+            *material = desired.clone();
+        }
+    }
+}
@@ -5,10 +5,10 @@ use ndarray::prelude::*;
 use rand::prelude::*;
 
 use super::{
-    block::{Block, BlockEvent},
+    block::{Block, BlockEvent, Marking},
     GamePiece, GameResult, GameState,
 };
-use crate::{FieldSettings, GameSettings, Safety};
+use crate::{BoardCode, FieldSettings, GameSettings, Neighborhood, Safety};
 
 pub struct FieldPlugin;
 impl Plugin for FieldPlugin {
@@ -21,6 +21,12 @@ impl Plugin for FieldPlugin {
                 .after(super::block::handle_ray_events)
                 .run_if(GameState::playable()),
         );
+        app.add_systems(
+            Update,
+            check_markings
+                .after(super::block::handle_block_events)
+                .run_if(in_state(GameState::GamePlaying)),
+        );
         app.add_systems(OnEnter(GameState::GameOver), reveal_all);
         app.add_event::<FieldEvent>();
     }
@@ -83,23 +89,109 @@ impl Display for FieldIndex {
     }
 }
 
+/// Difficulty metrics of a generated [Minefield].
+#[derive(Debug, Default, Clone)]
+pub struct BoardAnalysis {
+    /// "Bechtel's Board Benchmark Value": the minimum number of clicks needed to
+    /// clear the field. Each opening counts once, plus each numbered block that is
+    /// not revealed by an opening.
+    pub bbbv: usize,
+    /// Number of connected regions of blocks with no adjacent mines.
+    pub openings: usize,
+    /// Sizes of the connected groups of numbered blocks not revealed by any opening.
+    pub island_sizes: Vec<usize>,
+}
+
 #[derive(Component)]
 pub struct Minefield {
     cells: Array3<Cell>,
     density: f64,
+    /// Exact number of mines, used instead of the density if set
+    mine_count: Option<usize>,
     safety: Safety,
+    neighborhood: Neighborhood,
+    /// Seed for mine placement, so that the same game can be recreated
+    seed: u32,
+    /// Analysis of the field, available once it has been initialized
+    analysis: Option<BoardAnalysis>,
 }
 impl Minefield {
-    /// Initialize the [Minefield], placing mines randomly according to [Minefield::density].
+    /// Get a code that can be used to recreate this game.
+    pub fn board_code(&self) -> BoardCode {
+        let (i, j, k) = self.cells.dim();
+        BoardCode {
+            field_size: [i, j, k],
+            mine_density: self.density as f32,
+            mine_count: self.mine_count,
+            safety: self.safety,
+            neighborhood: self.neighborhood,
+            seed: self.seed,
+        }
+    }
+    pub fn analysis(&self) -> Option<&BoardAnalysis> {
+        self.analysis.as_ref()
+    }
+    /// Get what the cell at `index` contains, once the field has been initialized.
+    pub fn contains(&self, index: [usize; 3]) -> Option<Contains> {
+        self.analysis.as_ref()?;
+        self.cells.get(index).map(|cell| cell.contains)
+    }
+    /// Get the total number of mines in the field, once it has been initialized.
+    pub fn num_mines(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|cell| matches!(cell.contains, Contains::Mine))
+            .count()
+    }
+    /// Create an initialized field with mines at exactly `mines`.
+    #[cfg(test)]
+    pub(super) fn with_mines(field_size: [usize; 3], mines: &[[usize; 3]]) -> Self {
+        let mut field = Self {
+            cells: Array3::default(field_size),
+            density: 0.0,
+            mine_count: Some(mines.len()),
+            safety: Safety::Random,
+            neighborhood: Neighborhood::Full,
+            seed: 0,
+            analysis: None,
+        };
+        for index in mines {
+            field.cells[*index].contains = Contains::Mine;
+        }
+        field.count_adjacent_mines();
+        field.analysis = Some(field.analyze());
+        field
+    }
+    /// Get the indices of the cells adjacent to `index`.
+    pub(super) fn adjacent(&self, index: [usize; 3]) -> Vec<[usize; 3]> {
+        let mut adjacent = vec![];
+        self.foreach_adjacent(index, |adj_index| {
+            let (i, j, k) = *adj_index;
+            adjacent.push([i, j, k]);
+        });
+        adjacent
+    }
+    /// Initialize the [Minefield], placing mines randomly according to [Minefield::density],
+    /// or exactly [Minefield::mine_count] mines if set.
     fn initialize(&mut self, blocks: &Query<(Entity, &Block)>, click_location: FieldIndex) {
         // Save Block ids
         for (entity, block) in blocks {
             self.cells[block.index()].block = Some(entity)
         }
         info!("Creating minefield");
-        let mut rng = rand::thread_rng();
+        self.place_mines(click_location);
+        self.count_adjacent_mines();
+    }
+    /// Place mines in an order chosen by the seed. The first click only moves mines out of
+    /// the cells it keeps safe, so a board code recreates the same game when the same block
+    /// is clicked first.
+    fn place_mines(&mut self, click_location: FieldIndex) {
+        let mut rng = StdRng::seed_from_u64(self.seed.into());
         let num_blocks = self.cells.iter().count();
-        let num_mines = (num_blocks as f64 * self.density) as usize;
+        let num_mines = self
+            .mine_count
+            .unwrap_or((num_blocks as f64 * self.density) as usize)
+            .min(num_blocks);
         debug!(
             "Density {} => num_mines = {}/{}",
             self.density, num_mines, num_blocks
@@ -116,38 +208,29 @@ impl Minefield {
                 safe
             }
         };
-        // Sort remaining potential mine locations in random order
-        let mut random_cells: Vec<_> = self
-            .cells
-            .indexed_iter()
-            .map(|(i, _)| i.into())
-            .filter(|i| {
-                let safe = safe_cells.contains(i);
-                if safe {
-                    debug!("Ignoring safe cell {i}");
-                }
-                !safe
-            })
-            .collect();
+        // Sort every cell in random order, regardless of the click
+        let mut random_cells: Vec<FieldIndex> =
+            self.cells.indexed_iter().map(|(i, _)| i.into()).collect();
         random_cells.shuffle(&mut rng);
-        // Place mines
-        let mut mines_to_place = num_mines;
-        let num_cells = random_cells.len();
-        // Place mines randomly until there is exactly as many cells left as mines,
-        // then just fill the rest.
-        // Because we're iterating in random order, this will be fine.
+        let (mines, rest) = random_cells.split_at(num_mines);
+        // Mines in safe cells move to the next free cells in the same order.
         // Not guaranteed to place exactly num_mines mines; we prioritize the safety setting.
-        for (n, index) in random_cells.into_iter().enumerate() {
-            if mines_to_place == 0 {
-                break;
-            }
-            let cells_remaining = num_cells - n;
-            if cells_remaining <= mines_to_place || rng.gen_bool(self.density) {
-                self.cells[*index].contains = Contains::Mine;
-                mines_to_place -= 1;
-            }
+        let mut free_cells = rest.iter().filter(|i| !safe_cells.contains(i));
+        for index in mines {
+            let index = if safe_cells.contains(index) {
+                debug!("Moving mine out of safe cell {index}");
+                match free_cells.next() {
+                    Some(free) => free,
+                    None => continue,
+                }
+            } else {
+                index
+            };
+            self.cells[**index].contains = Contains::Mine;
         }
-        // Determine adjacent value in each cell
+    }
+    /// Determine the number of adjacent mines in each empty cell.
+    fn count_adjacent_mines(&mut self) {
         let mines: Vec<_> = self
             .cells
             .indexed_iter()
@@ -158,32 +241,15 @@ impl Minefield {
             .collect();
         for index in mines {
             debug!("Placed mine at {index:?}");
-            let (i, j, k) = index;
-            let mut increment_adjacent = |i_off, j_off, k_off| {
-                let adj_index = (
-                    i.wrapping_add_signed(i_off),
-                    j.wrapping_add_signed(j_off),
-                    k.wrapping_add_signed(k_off),
-                );
-                if let Some(adj) = self.cells.get_mut(adj_index) {
-                    if let Contains::Empty {
-                        ref mut adjacent_mines,
-                    } = adj.contains
-                    {
-                        debug!("Increment adjacent at {adj_index:?}");
-                        *adjacent_mines += 1;
-                    }
-                }
-            };
-            for i_off in -1..=1 {
-                for j_off in -1..=1 {
-                    for k_off in -1..=1 {
-                        // The block at index is not adjacent to itself
-                        if i_off == 0 && j_off == 0 && k_off == 0 {
-                            continue;
-                        }
-                        increment_adjacent(i_off, j_off, k_off);
-                    }
+            let mut adjacent = vec![];
+            self.foreach_adjacent(index, |adj_index| adjacent.push(adj_index));
+            for adj_index in adjacent {
+                if let Contains::Empty {
+                    ref mut adjacent_mines,
+                } = self.cells[*adj_index].contains
+                {
+                    debug!("Increment adjacent at {adj_index}");
+                    *adjacent_mines += 1;
                 }
             }
         }
@@ -196,8 +262,9 @@ impl Minefield {
         for i_off in -1..=1 {
             for j_off in -1..=1 {
                 for k_off in -1..=1 {
-                    // The block at index is not adjacent to itself
-                    if i_off == 0 && j_off == 0 && k_off == 0 {
+                    // The block at index is not adjacent to itself, and the
+                    // neighborhood may exclude some others
+                    if !self.neighborhood.contains([i_off, j_off, k_off]) {
                         continue;
                     }
                     // Get a block adjacent to index
@@ -215,56 +282,117 @@ impl Minefield {
             }
         }
     }
+    /// Reveal the blocks around an empty block, spreading through any others that are
+    /// also empty. Blocks are revealed in order of distance from `index`, so that the
+    /// reveal can be shown as a wave.
     fn reveal_adjacent(
         &mut self,
         index: (usize, usize, usize),
         block_events: &mut EventWriter<BlockEvent>,
     ) {
-        let (i, j, k) = index;
-        for i_off in -1..=1 {
-            for j_off in -1..=1 {
-                for k_off in -1..=1 {
-                    // The block at index is not adjacent to itself
-                    if i_off == 0 && j_off == 0 && k_off == 0 {
-                        continue;
+        let mut revealed = vec![];
+        let mut queue = std::collections::VecDeque::from([(FieldIndex(index), 0)]);
+        while let Some((index, distance)) = queue.pop_front() {
+            let mut adjacent = vec![];
+            self.foreach_adjacent(index, |adj_index| adjacent.push(adj_index));
+            for adj_index in adjacent {
+                let adj = &mut self.cells[*adj_index];
+                // If the adjacent block is already revealed, don't bother
+                if adj.revealed {
+                    continue;
+                }
+                let contains = adj.contains;
+                // Don't reveal mines
+                let Contains::Empty { adjacent_mines } = contains else {
+                    continue;
+                };
+                // Get the entity to send with the message
+                let Some(adj_id) = adj.block else {
+                    continue;
+                };
+                adj.revealed = true;
+                revealed.push((adj_id, contains, distance + 1));
+                // Spread only if this block was not adjacent to any mines
+                if adjacent_mines == 0 {
+                    queue.push_back((adj_index, distance + 1));
+                }
+            }
+        }
+        let max_distance = revealed.last().map_or(1, |(_, _, distance)| *distance);
+        for (adj_id, contains, distance) in revealed {
+            // Send a message to reveal this block
+            let event = BlockEvent::Clear(adj_id, contains, distance as f32 / max_distance as f32);
+            debug!("Send {event:?}");
+            block_events.send(event);
+        }
+    }
+    /// Analyze the placement of mines. Only meaningful after [Minefield::initialize].
+    pub fn analyze(&self) -> BoardAnalysis {
+        let mut visited = Array3::from_elem(self.cells.dim(), false);
+        let mut openings = 0;
+        // Each opening is cleared with a single click, revealing its bordering numbers as well
+        for (index, cell) in self.cells.indexed_iter() {
+            if visited[index] || !matches!(cell.contains, Contains::Empty { adjacent_mines: 0 }) {
+                continue;
+            }
+            openings += 1;
+            visited[index] = true;
+            let mut to_visit = vec![FieldIndex::from(index)];
+            while let Some(next) = to_visit.pop() {
+                self.foreach_adjacent(next, |adj_index| {
+                    if visited[*adj_index] {
+                        return;
                     }
-                    // Get a block adjacent to index
-                    let adj_index = (
-                        i.wrapping_add_signed(i_off),
-                        j.wrapping_add_signed(j_off),
-                        k.wrapping_add_signed(k_off),
-                    );
-                    // Make sure we have a valid adj_index
-                    let Some(adj) = self.cells.get_mut(adj_index) else {
-                        continue;
-                    };
-                    // If the adjacent block is already revealed, don't bother
-                    // TODO: maybe not good idea?
-                    if adj.revealed {
-                        continue;
+                    if let Contains::Empty { adjacent_mines } = self.cells[*adj_index].contains {
+                        visited[*adj_index] = true;
+                        if adjacent_mines == 0 {
+                            to_visit.push(adj_index);
+                        }
                     }
-                    let contains = adj.contains;
-                    // Don't reveal mines
-                    let Contains::Empty { adjacent_mines } = contains else {
-                        continue;
-                    };
-                    // Get the entity to send with the message
-                    let Some(adj_id) = adj.block else {
-                        continue;
-                    };
-                    adj.revealed = true;
-                    // Send a message to reveal this block
-                    let event = BlockEvent::Clear(adj_id, contains);
-                    debug!("Send {event:?}");
-                    block_events.send(event);
-                    // Recurse only if this block was not adjacent to any mines
-                    if adjacent_mines == 0 {
-                        self.reveal_adjacent(adj_index, block_events);
+                });
+            }
+        }
+        // Any remaining numbered blocks need one click each, grouped into islands
+        let mut island_sizes = vec![];
+        for (index, cell) in self.cells.indexed_iter() {
+            if visited[index] || matches!(cell.contains, Contains::Mine) {
+                continue;
+            }
+            let mut size = 0;
+            visited[index] = true;
+            let mut to_visit = vec![FieldIndex::from(index)];
+            while let Some(next) = to_visit.pop() {
+                size += 1;
+                self.foreach_adjacent(next, |adj_index| {
+                    if !visited[*adj_index]
+                        && !matches!(self.cells[*adj_index].contains, Contains::Mine)
+                    {
+                        visited[*adj_index] = true;
+                        to_visit.push(adj_index);
                     }
-                }
+                });
             }
+            island_sizes.push(size);
+        }
+        BoardAnalysis {
+            bbbv: openings + island_sizes.iter().sum::<usize>(),
+            openings,
+            island_sizes,
         }
     }
+    /// Return true iff every mine is marked, and no hidden non-mine is marked.
+    fn correctly_marked(&self, blocks: &Query<&Block>) -> bool {
+        self.cells.iter().all(|cell| {
+            let marked = cell
+                .block
+                .and_then(|entity| blocks.get(entity).ok())
+                .is_some_and(|block| block.marking() == Marking::Mine);
+            match cell.contains {
+                Contains::Mine => marked,
+                Contains::Empty { .. } => cell.revealed || !marked,
+            }
+        })
+    }
     /// Return true iff the Minefield has been fully revealed (victory condition)
     fn fully_revealed(&self) -> bool {
         for cell in &self.cells {
@@ -278,13 +406,19 @@ impl Minefield {
 
 fn spawn(
     game_settings: Res<GameSettings>,
-    field_settings: Res<FieldSettings>,
+    mut field_settings: ResMut<FieldSettings>,
     mut commands: Commands,
 ) {
+    // Consume the seed, so a replayed board code doesn't stick to the next New Game
+    let seed = field_settings.seed.take();
     let field = Minefield {
         cells: Array3::default(field_settings.field_size),
         density: field_settings.mine_density.into(),
+        mine_count: field_settings.mine_count,
         safety: game_settings.safety,
+        neighborhood: game_settings.neighborhood,
+        seed: seed.unwrap_or_else(random),
+        analysis: None,
     };
     commands.spawn((field, GamePiece));
 }
@@ -312,18 +446,25 @@ pub(super) fn handle_field_events(
                 let Some(cell) = field.cells.get_mut(*index) else {
                     continue;
                 };
+                // An earlier clear in the same chord may have cascaded into this block
+                if cell.revealed {
+                    continue;
+                }
                 cell.revealed = true;
                 if matches!(game_state.get(), GameState::GameStart) {
                     debug!("Transition to GameState::Playing");
                     next_state.set(GameState::GamePlaying);
                     field.initialize(&blocks, index.into());
+                    let analysis = field.analyze();
+                    info!("Field has 3BV {}", analysis.bbbv);
+                    field.analysis = Some(analysis);
                 }
                 // Get the updated field
                 let Some(cell) = field.cells.get_mut(*index) else {
                     continue;
                 };
                 let contains = cell.contains;
-                let event = BlockEvent::Clear(cell.block.unwrap(), contains);
+                let event = BlockEvent::Clear(cell.block.unwrap(), contains, 0.0);
                 debug!("Send {event:?}");
                 block_events.send(event);
                 if matches!(contains, Contains::Empty { adjacent_mines } if adjacent_mines == 0) {
@@ -340,9 +481,147 @@ pub(super) fn handle_field_events(
     }
 }
 
+/// Check whether the player's markings end the game, according to [GameSettings].
+fn check_markings(
+    game_settings: Res<GameSettings>,
+    changed_blocks: Query<(), Changed<Block>>,
+    blocks: Query<&Block>,
+    mut field: Query<&mut Minefield>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut game_result: ResMut<GameResult>,
+    mut block_events: EventWriter<BlockEvent>,
+) {
+    if !game_settings.win_by_marking && !game_settings.auto_clear_marked {
+        return;
+    }
+    // Markings can only change if a block did
+    if changed_blocks.is_empty() {
+        return;
+    }
+    let mut field = field.single_mut();
+    if !field.correctly_marked(&blocks) {
+        return;
+    }
+    if game_settings.auto_clear_marked {
+        info!("All mines marked, clearing remaining blocks");
+        for cell in field.cells.iter_mut() {
+            if cell.revealed || matches!(cell.contains, Contains::Mine) {
+                continue;
+            }
+            let Some(block) = cell.block else {
+                continue;
+            };
+            cell.revealed = true;
+            block_events.send(BlockEvent::Clear(block, cell.contains, 0.0));
+        }
+    }
+    info!("Victory!");
+    debug!("Transition to GameState::Ended");
+    *game_result = GameResult::Victory;
+    next_state.set(GameState::GameOver);
+}
+
 fn reveal_all(mut field: Query<&mut Minefield>, mut block_events: EventWriter<BlockEvent>) {
     for cell in field.single_mut().cells.iter_mut() {
         cell.revealed = true;
         block_events.send(BlockEvent::EndReveal(cell.block.unwrap(), cell.contains));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded_field(field_size: [usize; 3], mine_count: usize, safety: Safety) -> Minefield {
+        Minefield {
+            cells: Array3::default(field_size),
+            density: 0.0,
+            mine_count: Some(mine_count),
+            safety,
+            neighborhood: Neighborhood::Full,
+            seed: 1234,
+            analysis: None,
+        }
+    }
+
+    fn mines(field: &Minefield) -> Vec<[usize; 3]> {
+        field
+            .cells
+            .indexed_iter()
+            .filter(|(_, cell)| matches!(cell.contains, Contains::Mine))
+            .map(|((i, j, k), _)| [i, j, k])
+            .collect()
+    }
+
+    #[test]
+    fn first_click_only_moves_mines_out_of_safe_cells() {
+        let mut unsafe_field = seeded_field([6, 6, 6], 40, Safety::Random);
+        unsafe_field.place_mines((0, 0, 0).into());
+        let unsafe_mines = mines(&unsafe_field);
+        for click in [[0, 0, 0], [3, 3, 3], [5, 2, 1]] {
+            let mut field = seeded_field([6, 6, 6], 40, Safety::Clear);
+            field.place_mines(click.into());
+            let mut safe = field.adjacent(click);
+            safe.push(click);
+            let mines = mines(&field);
+            assert_eq!(mines.len(), 40);
+            assert!(mines.iter().all(|index| !safe.contains(index)));
+            // Everywhere else, the seed alone decides where the mines are
+            for index in unsafe_mines.iter().filter(|index| !safe.contains(index)) {
+                assert!(mines.contains(index), "mine at {index:?} moved");
+            }
+        }
+    }
+
+    #[test]
+    fn safety_gives_way_when_field_is_full() {
+        let mut field = seeded_field([3, 3, 1], 9, Safety::Safe);
+        field.place_mines((1, 1, 0).into());
+        let mines = mines(&field);
+        assert_eq!(mines.len(), 8);
+        assert!(!mines.contains(&[1, 1, 0]));
+    }
+
+    #[test]
+    fn analysis_of_field_without_mines() {
+        let analysis = Minefield::with_mines([4, 3, 2], &[]).analyze();
+        assert_eq!(analysis.openings, 1);
+        assert!(analysis.island_sizes.is_empty());
+        assert_eq!(analysis.bbbv, 1);
+    }
+
+    #[test]
+    fn analysis_of_field_of_mines() {
+        let mines: Vec<_> = (0..3)
+            .flat_map(|i| (0..2).map(move |j| [i, j, 0]))
+            .collect();
+        let analysis = Minefield::with_mines([3, 2, 1], &mines).analyze();
+        assert_eq!(analysis.openings, 0);
+        assert!(analysis.island_sizes.is_empty());
+        assert_eq!(analysis.bbbv, 0);
+    }
+
+    #[test]
+    fn analysis_counts_openings_and_islands() {
+        // A row of 7: 0 0 1 * 1 0 0, where each 1 borders an opening
+        let analysis = Minefield::with_mines([7, 1, 1], &[[3, 0, 0]]).analyze();
+        assert_eq!(analysis.openings, 2);
+        assert!(analysis.island_sizes.is_empty());
+        assert_eq!(analysis.bbbv, 2);
+
+        // A row of 7: 1 * 2 * 1 0 0, where the first two numbers need a click each
+        let analysis = Minefield::with_mines([7, 1, 1], &[[1, 0, 0], [3, 0, 0]]).analyze();
+        assert_eq!(analysis.openings, 1);
+        assert_eq!(analysis.island_sizes, vec![1, 1]);
+        assert_eq!(analysis.bbbv, 3);
+    }
+
+    #[test]
+    fn analysis_groups_numbers_into_islands() {
+        // A row of 4: * 1 1 *, where the two numbers touch but neither opens the other
+        let analysis = Minefield::with_mines([4, 1, 1], &[[0, 0, 0], [3, 0, 0]]).analyze();
+        assert_eq!(analysis.openings, 0);
+        assert_eq!(analysis.island_sizes, vec![2]);
+        assert_eq!(analysis.bbbv, 2);
+    }
+}
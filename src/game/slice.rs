@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+
+use super::block::Block;
+use crate::{FieldSettings, GameState, InputEvent};
+
+pub struct SlicePlugin;
+impl Plugin for SlicePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FieldSlice>();
+        app.add_systems(OnEnter(GameState::GameStart), reset);
+        app.add_systems(
+            Update,
+            (handle_slice_input, apply_slice)
+                .chain()
+                .before(super::camera::camera_controls)
+                .run_if(GameState::in_game()),
+        );
+    }
+}
+
+/// Which side of the slicing plane is hidden.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SliceSide {
+    /// Hide blocks with a greater index than the plane.
+    #[default]
+    Above,
+    /// Hide blocks with a lesser index than the plane.
+    Below,
+}
+
+/// A plane through the field, hiding all blocks on one side of it
+/// so the interior can be seen.
+#[derive(Debug, Default, Resource)]
+pub struct FieldSlice {
+    /// Axis perpendicular to the slicing plane, or `None` to show every block.
+    pub axis: Option<usize>,
+    /// Index of the last visible layer along the axis.
+    pub layer: usize,
+    /// Which side of the plane is hidden.
+    pub hidden_side: SliceSide,
+}
+impl FieldSlice {
+    /// Slice along a different axis, starting with every block visible.
+    pub fn set_axis(&mut self, axis: Option<usize>, field_size: [usize; 3]) {
+        self.axis = axis;
+        if let Some(axis) = axis {
+            self.layer = match self.hidden_side {
+                SliceSide::Above => field_size[axis] - 1,
+                SliceSide::Below => 0,
+            };
+        }
+    }
+    /// Return true iff the block at `index` is not hidden by the slice.
+    pub fn shows(&self, index: [usize; 3]) -> bool {
+        let Some(axis) = self.axis else {
+            return true;
+        };
+        match self.hidden_side {
+            SliceSide::Above => index[axis] <= self.layer,
+            SliceSide::Below => index[axis] >= self.layer,
+        }
+    }
+}
+
+fn reset(mut slice: ResMut<FieldSlice>) {
+    *slice = FieldSlice::default();
+}
+
+fn handle_slice_input(
+    field_settings: Res<FieldSettings>,
+    mut input_events: EventReader<InputEvent>,
+    mut slice: ResMut<FieldSlice>,
+) {
+    for input_event in input_events.read() {
+        match input_event {
+            InputEvent::CycleSliceAxis => {
+                let axis = match slice.axis {
+                    None => Some(0),
+                    Some(axis) if axis < 2 => Some(axis + 1),
+                    Some(_) => None,
+                };
+                slice.set_axis(axis, field_settings.field_size);
+                debug!("Slice along axis {:?}", slice.axis);
+            }
+            InputEvent::MoveSlice { delta } => {
+                let Some(axis) = slice.axis else {
+                    continue;
+                };
+                let max_layer = field_settings.field_size[axis] - 1;
+                slice.layer = slice.layer.saturating_add_signed(*delta).min(max_layer);
+                debug!("Slice at layer {}", slice.layer);
+            }
+            InputEvent::FlipSlice => {
+                slice.hidden_side = match slice.hidden_side {
+                    SliceSide::Above => SliceSide::Below,
+                    SliceSide::Below => SliceSide::Above,
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Hide blocks that are sliced away.
+fn apply_slice(
+    slice: Res<FieldSlice>,
+    mut blocks: Query<(&Block, &mut Visibility)>,
+    new_blocks: Query<(), Added<Block>>,
+) {
+    if !slice.is_changed() && new_blocks.is_empty() {
+        return;
+    }
+    for (block, mut visibility) in &mut blocks {
+        let shown = slice.shows(block.index());
+        visibility.set_if_neq(if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use super::block::{Block, Marking};
+use super::minefield::{Contains, Minefield};
+use super::{GameState, GameStats};
+use crate::GameSettings;
+
+/// Checks the player's moves against the true contents of the field while training.
+pub struct TrainingPlugin;
+impl Plugin for TrainingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Mistakes>();
+        app.add_systems(OnEnter(GameState::GameStart), start.after(super::cleanup));
+        app.add_systems(
+            Update,
+            check_mistakes
+                .after(super::block::handle_block_events)
+                .run_if(in_state(GameState::GamePlaying)),
+        );
+    }
+}
+
+/// Mistakes currently on the field, found using the true contents of the field.
+#[derive(Debug, Default, Resource)]
+pub struct Mistakes {
+    /// Blocks marked as mines that are not mines.
+    pub wrong_marks: Vec<[usize; 3]>,
+    /// Hidden, unmarked blocks next to a number whose mines are all marked.
+    pub missed_safe: Vec<[usize; 3]>,
+    /// Blocks already counted as mistakes this game, so each is counted once.
+    counted: HashSet<[usize; 3]>,
+}
+impl Mistakes {
+    pub fn contains(&self, index: [usize; 3]) -> bool {
+        self.wrong_marks.contains(&index) || self.missed_safe.contains(&index)
+    }
+    /// Describe the current mistakes for the HUD.
+    pub fn message(&self) -> Option<String> {
+        match (self.wrong_marks.len(), self.missed_safe.len()) {
+            (0, 0) => None,
+            (1, _) => Some("Marked a block that is not a mine".to_string()),
+            (n, _) if n > 1 => Some(format!("Marked {n} blocks that are not mines")),
+            (_, 1) => Some("1 block next to a satisfied number is safe".to_string()),
+            (_, n) => Some(format!("{n} blocks next to satisfied numbers are safe")),
+        }
+    }
+}
+
+/// Tag the game as a training game, and forget mistakes from the last one.
+fn start(
+    game_settings: Res<GameSettings>,
+    mut stats: ResMut<GameStats>,
+    mut mistakes: ResMut<Mistakes>,
+) {
+    stats.training = game_settings.training;
+    *mistakes = Mistakes::default();
+}
+
+/// Find wrongly marked blocks and safe blocks left next to satisfied numbers whenever
+/// blocks are revealed or marked.
+fn check_mistakes(
+    field: Query<&Minefield>,
+    blocks: Query<&Block>,
+    changed_blocks: Query<&Block, Changed<Block>>,
+    mut stats: ResMut<GameStats>,
+    mut mistakes: ResMut<Mistakes>,
+) {
+    if !stats.training || changed_blocks.is_empty() {
+        return;
+    }
+    let field = field.single();
+    let blocks: HashMap<_, _> = blocks.iter().map(|block| (block.index(), block)).collect();
+    let is_mine = |index| matches!(field.contains(index), Some(Contains::Mine));
+
+    let mut wrong_marks: Vec<_> = blocks
+        .values()
+        .filter(|block| block.marking() == Marking::Mine && !is_mine(block.index()))
+        .map(|block| block.index())
+        .collect();
+    wrong_marks.sort_unstable();
+
+    let mut missed_safe = vec![];
+    for (&index, block) in &blocks {
+        let Some(Contains::Empty { adjacent_mines }) = block.revealed() else {
+            continue;
+        };
+        let adjacent = field.adjacent(index);
+        let marked_mines = adjacent
+            .iter()
+            .filter(|adj| is_mine(**adj))
+            .filter(|adj| {
+                blocks
+                    .get(*adj)
+                    .is_some_and(|b| b.marking() == Marking::Mine)
+            })
+            .count();
+        if adjacent_mines == 0 || marked_mines < adjacent_mines as usize {
+            continue;
+        }
+        missed_safe.extend(adjacent.into_iter().filter(|adj| {
+            blocks
+                .get(adj)
+                .is_some_and(|b| b.is_hidden() && b.marking() == Marking::None)
+        }));
+    }
+    missed_safe.sort_unstable();
+    missed_safe.dedup();
+
+    // Safe blocks only count as mistakes once the player moves on without clearing
+    // any of them
+    let moved_on = !changed_blocks
+        .iter()
+        .any(|block| mistakes.missed_safe.contains(&block.index()));
+    let left_safe = mistakes
+        .missed_safe
+        .iter()
+        .filter(|index| moved_on && missed_safe.contains(index))
+        .copied()
+        .collect::<Vec<_>>();
+    for index in wrong_marks.iter().copied().chain(left_safe) {
+        if mistakes.counted.insert(index) {
+            info!("Training mistake at {index:?}");
+            stats.mistakes += 1;
+        }
+    }
+    if mistakes.wrong_marks != wrong_marks || mistakes.missed_safe != missed_safe {
+        mistakes.wrong_marks = wrong_marks;
+        mistakes.missed_safe = missed_safe;
+    }
+}
@@ -1,15 +1,34 @@
 use bevy::input::{
     keyboard::KeyboardInput,
     mouse::{MouseButtonInput, MouseMotion, MouseWheel},
+    InputSystem,
 };
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
+
+mod bindings;
+mod gamepad;
+mod touch;
+
+pub use bindings::{Action, Binding, InputBindings, Modifiers, PhysicalInput};
 
 pub struct InputPlugin;
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<InputEvent>()
-            .add_systems(PreUpdate, (mouse_input, keyboard_input));
+            .init_resource::<InputBindings>()
+            .add_systems(Startup, load_bindings)
+            .add_systems(
+                PreUpdate,
+                (
+                    mouse_input,
+                    keyboard_input,
+                    gamepad::gamepad_input,
+                    touch::touch_input,
+                )
+                    .after(InputSystem),
+            );
     }
 }
 
@@ -32,56 +51,210 @@ impl From<Vec2> for ScreenPosition {
 
 #[derive(Debug, Event)]
 pub enum InputEvent {
-    /// Clear a block at a screen position (default: left click).
+    /// Clear a block at a screen position (default: left click, or gamepad South with
+    /// the reticle at the center of the screen).
     ClearBlock(ScreenPosition),
     /// Mark a block at a screen location (default: Right click).
     MarkBlock(ScreenPosition),
+    /// Clear all unmarked blocks adjacent to the revealed number at a screen location,
+    /// if enough adjacent blocks are marked (default: Shift + left click).
+    ChordBlock(ScreenPosition),
     /// Select a button or object at a position on the screen (default: left click).
     Select(ScreenPosition),
+    /// The pointer moved to a position on the screen, or left it (default: mouse cursor).
+    PointAt(Option<ScreenPosition>),
     /// Rotate the camera. `delta.x` is horizontal rotation, and `delta.y` is vertical
     /// (default: Middle mouse button and movement).
     /// Relative to window size.
     RotateCamera { delta: Vec2 },
+    /// Move the camera and the point it orbits around, relative to window size
+    /// (default: Shift + middle mouse button and movement, or arrow keys).
+    PanCamera { delta: Vec2 },
+    /// Orbit around the block being pointed at or selected, or around the center of the
+    /// field if there is none (default: G, or double-click the middle mouse button).
+    FocusBlock,
+    /// Focus on and select the next hidden block adjacent to a revealed number (default N).
+    NextFrontier,
+    /// Look at the field from a direction, where each component of `direction` is -1, 0
+    /// or 1. Faces have one nonzero component, and corners have three (default: Numpad
+    /// 1/3/7 for +Z/+X/+Y, with Ctrl for the opposite side, Numpad 9/8/6/4 for the
+    /// corners above the field, with Ctrl for those below, or the orientation cube).
+    SnapView { direction: IVec3 },
+    /// Switch between perspective and orthographic projection (default O).
+    ToggleProjection,
     /// Zoom the camera (default mouse wheel up/down).
     /// `delta` indicates zoom direction and magnitude: positive zooms in, and negative zooms out.
     ZoomCamera { delta: f32 },
+    /// Change the spacing between blocks (default Ctrl + mouse wheel up/down).
+    /// `delta` indicates direction and magnitude: positive spreads blocks apart, and negative
+    /// brings them together.
+    SpreadBlocks { delta: f32 },
     /// Pause the game is a specific key is pressed (default ESC) or if the window
     /// (or app) loses focus.
     Pause,
+    /// Move the block cursor one block in a direction relative to the camera (default WASD
+    /// for left/right/up/down, and QE for away/toward). `direction` is in view space,
+    /// with +X right, +Y up and +Z toward the camera.
+    MoveCursor { direction: Vec3 },
+    /// Clear the block under the block cursor (default Space).
+    ClearSelected,
+    /// Mark the block under the block cursor (default F).
+    MarkSelected,
+    /// Clear all unmarked blocks adjacent to the revealed number under the block cursor,
+    /// if enough adjacent blocks are marked (default C).
+    ChordSelected,
+    /// Clear the block at a field index, from the layer panel (default: left click a cell).
+    ClearAt { index: [usize; 3] },
+    /// Mark the block at a field index, from the layer panel (default: right click a cell).
+    MarkAt { index: [usize; 3] },
+    /// Chord on the revealed number at a field index, from the layer panel
+    /// (default: left click a number).
+    ChordAt { index: [usize; 3] },
+    /// Show or hide the layer panel (default L).
+    ToggleLayerPanel,
+    /// Finish any explosion or victory effects immediately (default Enter).
+    SkipEffects,
+    /// Change which axis the field is sliced along, or stop slicing (default X).
+    CycleSliceAxis,
+    /// Move the slicing plane by a number of layers (default PageUp/PageDown).
+    MoveSlice { delta: isize },
+    /// Swap which side of the slicing plane is hidden (default V).
+    FlipSlice,
 }
 
 /// Conversion factor between scroll by pixels and scroll by lines, for consistent
 /// behavior on different devices.
 const SCROLL_PIXELS_PER_LINE: f32 = 8.0;
+/// Distance to pan the camera for each key press, relative to window size.
+const PAN_STEP: f32 = 0.05;
+/// Maximum seconds between two clicks for them to count as a double-click.
+const DOUBLE_CLICK_SECS: f32 = 0.3;
+
+/// Convert a scroll or pinch in pixels to lines, the unit of [InputEvent::ZoomCamera]
+/// and [InputEvent::SpreadBlocks].
+fn pixels_to_lines(pixels: f32) -> f32 {
+    pixels / SCROLL_PIXELS_PER_LINE
+}
+
+fn load_bindings(mut commands: Commands) {
+    commands.insert_resource(InputBindings::load());
+}
+
+/// Get the event for a button-like action. Actions on blocks need a position on the screen.
+fn action_event(action: Action, cursor_pos: Option<ScreenPosition>) -> Option<InputEvent> {
+    let move_cursor = |direction| Some(InputEvent::MoveCursor { direction });
+    let pan = |delta: Vec2| {
+        Some(InputEvent::PanCamera {
+            delta: delta * PAN_STEP,
+        })
+    };
+    let snap = |direction| Some(InputEvent::SnapView { direction });
+    match action {
+        Action::ClearBlock => cursor_pos.map(InputEvent::ClearBlock),
+        Action::MarkBlock => cursor_pos.map(InputEvent::MarkBlock),
+        Action::ChordBlock => cursor_pos.map(InputEvent::ChordBlock),
+        // Handled as mouse motion and scrolling
+        Action::RotateCamera | Action::PanCamera | Action::ZoomCamera | Action::SpreadBlocks => {
+            None
+        }
+        Action::PanLeft => pan(Vec2::X),
+        Action::PanRight => pan(Vec2::NEG_X),
+        Action::PanUp => pan(Vec2::Y),
+        Action::PanDown => pan(Vec2::NEG_Y),
+        Action::FocusBlock => Some(InputEvent::FocusBlock),
+        Action::NextFrontier => Some(InputEvent::NextFrontier),
+        Action::ViewPosX => snap(IVec3::X),
+        Action::ViewNegX => snap(IVec3::NEG_X),
+        Action::ViewPosY => snap(IVec3::Y),
+        Action::ViewNegY => snap(IVec3::NEG_Y),
+        Action::ViewPosZ => snap(IVec3::Z),
+        Action::ViewNegZ => snap(IVec3::NEG_Z),
+        Action::ViewPosXPosYPosZ => snap(IVec3::new(1, 1, 1)),
+        Action::ViewNegXPosYPosZ => snap(IVec3::new(-1, 1, 1)),
+        Action::ViewPosXPosYNegZ => snap(IVec3::new(1, 1, -1)),
+        Action::ViewNegXPosYNegZ => snap(IVec3::new(-1, 1, -1)),
+        Action::ViewPosXNegYPosZ => snap(IVec3::new(1, -1, 1)),
+        Action::ViewNegXNegYPosZ => snap(IVec3::new(-1, -1, 1)),
+        Action::ViewPosXNegYNegZ => snap(IVec3::new(1, -1, -1)),
+        Action::ViewNegXNegYNegZ => snap(IVec3::new(-1, -1, -1)),
+        Action::ToggleProjection => Some(InputEvent::ToggleProjection),
+        Action::ToggleLayerPanel => Some(InputEvent::ToggleLayerPanel),
+        Action::SkipEffects => Some(InputEvent::SkipEffects),
+        Action::Pause => Some(InputEvent::Pause),
+        Action::CursorLeft => move_cursor(Vec3::NEG_X),
+        Action::CursorRight => move_cursor(Vec3::X),
+        Action::CursorUp => move_cursor(Vec3::Y),
+        Action::CursorDown => move_cursor(Vec3::NEG_Y),
+        Action::CursorAway => move_cursor(Vec3::NEG_Z),
+        Action::CursorToward => move_cursor(Vec3::Z),
+        Action::ClearSelected => Some(InputEvent::ClearSelected),
+        Action::MarkSelected => Some(InputEvent::MarkSelected),
+        Action::ChordSelected => Some(InputEvent::ChordSelected),
+        Action::CycleSliceAxis => Some(InputEvent::CycleSliceAxis),
+        Action::SliceUp => Some(InputEvent::MoveSlice { delta: 1 }),
+        Action::SliceDown => Some(InputEvent::MoveSlice { delta: -1 }),
+        Action::FlipSlice => Some(InputEvent::FlipSlice),
+    }
+}
 
 /// Handle mouse input. All available events are consumed and accumulated into possibly fewer
 /// InputEvents for efficiency.
+#[allow(clippy::too_many_arguments)]
 fn mouse_input(
     mut mouse_wheel_events: EventReader<MouseWheel>,
     mut mouse_button_events: EventReader<MouseButtonInput>,
     mut mouse_motion_events: EventReader<MouseMotion>,
     mouse_button: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<InputBindings>,
     primary_window: Query<&Window, With<PrimaryWindow>>,
+    time: Res<Time>,
+    mut contexts: EguiContexts,
     mut input_events: EventWriter<InputEvent>,
+    mut last_cursor_pos: Local<Option<Vec2>>,
+    mut last_click: Local<Option<(MouseButton, f32)>>,
 ) {
     // Get the singular primary window. Multiple windows is not handled.
     let window = primary_window.single();
-    // Handle mouse motion events only if the rotate button (default MMB) is pressed
-    if mouse_button.pressed(MouseButton::Middle) {
+    let over_ui = contexts.ctx_mut().is_pointer_over_area();
+    // Only point at things outside of the UI
+    let cursor_pos = window.cursor_position().filter(|_| !over_ui);
+    if cursor_pos != *last_cursor_pos {
+        *last_cursor_pos = cursor_pos;
+        input_events.send(InputEvent::PointAt(cursor_pos.map(ScreenPosition::from)));
+    }
+    // Mouse input over a menu or HUD element is meant for that element
+    if over_ui {
+        mouse_wheel_events.clear();
+        mouse_button_events.clear();
+        mouse_motion_events.clear();
+        return;
+    }
+    let modifiers = Modifiers::pressed(&keyboard);
+    // Handle mouse motion events only if the rotate (default MMB) or pan
+    // (default Shift + MMB) input is held
+    let rotating = bindings.held(Action::RotateCamera, &keyboard, &mouse_button);
+    let panning = bindings.held(Action::PanCamera, &keyboard, &mouse_button);
+    if rotating || panning {
         // Collect all motion events into a single delta
         let mut delta = Vec2::ZERO;
         for motion_event in mouse_motion_events.read() {
             delta += motion_event.delta;
         }
-        // Only send a InputEvent::RotateCamera if the delta is nonzero
+        // Only send an event if the delta is nonzero
         if delta.length_squared() > 0.0 {
             // Scale based on window size
             let delta = Vec2::new(delta.x / window.width(), delta.y / window.height());
-            debug!("Send InputEvent::RotateCamera");
-            input_events.send(InputEvent::RotateCamera { delta });
+            if rotating {
+                debug!("Send InputEvent::RotateCamera");
+                input_events.send(InputEvent::RotateCamera { delta });
+            } else {
+                debug!("Send InputEvent::PanCamera");
+                input_events.send(InputEvent::PanCamera { delta });
+            }
         }
     } else {
-        // If the rotate button is not pressed, clear all rotation events
+        // If neither input is held, clear all motion events
         mouse_motion_events.clear();
     }
     // Handle scroll events
@@ -95,7 +268,7 @@ fn mouse_input(
                 scroll
             }
             bevy::input::mouse::MouseScrollUnit::Pixel => {
-                let lines = scroll * SCROLL_PIXELS_PER_LINE;
+                let lines = pixels_to_lines(scroll);
                 debug!("Scrolled {scroll} pixels ({lines} lines)");
                 lines
             }
@@ -103,10 +276,25 @@ fn mouse_input(
     }
     // Only send an event if the delta is nonzero
     if scroll_delta.abs() > 0.0 {
-        debug!("Send InputEvent::ZoomCamera");
-        input_events.send(InputEvent::ZoomCamera {
-            delta: scroll_delta,
-        });
+        let binding = Binding {
+            input: PhysicalInput::Wheel,
+            modifiers,
+        };
+        match bindings.action(binding) {
+            Some(Action::ZoomCamera) => {
+                debug!("Send InputEvent::ZoomCamera");
+                input_events.send(InputEvent::ZoomCamera {
+                    delta: scroll_delta,
+                });
+            }
+            Some(Action::SpreadBlocks) => {
+                debug!("Send InputEvent::SpreadBlocks");
+                input_events.send(InputEvent::SpreadBlocks {
+                    delta: scroll_delta,
+                });
+            }
+            _ => {}
+        }
     }
     // We don't care about mouse clicks if the mouse is not in the primary window
     let Some(cursor_pos) = window.cursor_position() else {
@@ -114,36 +302,61 @@ fn mouse_input(
     };
     // Handle mouse click events (default LMB or RMB)
     for mouse_button_event in mouse_button_events.read() {
-        if mouse_button_event.state.is_pressed() {
-            debug!("Click at {cursor_pos:?}");
-            match mouse_button_event.button {
-                MouseButton::Left => {
-                    debug!("Send InputEvent::ClearBlock");
-                    input_events.send(InputEvent::ClearBlock(cursor_pos.into()));
-                }
-                MouseButton::Right => {
-                    debug!("Send InputEvent::MarkBlock");
-                    input_events.send(InputEvent::MarkBlock(cursor_pos.into()));
-                }
-                _ => {}
-            };
+        if !mouse_button_event.state.is_pressed() {
+            continue;
+        }
+        debug!("Click at {cursor_pos:?}");
+        let button = mouse_button_event.button;
+        let binding = Binding {
+            input: PhysicalInput::Mouse(button),
+            modifiers,
+        };
+        let action = bindings.action(binding);
+        // Double-clicking the rotate input focuses on the block under the pointer
+        let now = time.elapsed_seconds();
+        let double_click = matches!(*last_click,
+            Some((last_button, last_time)) if last_button == button && now - last_time < DOUBLE_CLICK_SECS);
+        *last_click = Some((button, now));
+        if double_click && action == Some(Action::RotateCamera) {
+            *last_click = None;
+            debug!("Send InputEvent::FocusBlock");
+            input_events.send(InputEvent::FocusBlock);
+            continue;
+        }
+        let event = action.and_then(|action| action_event(action, Some(cursor_pos.into())));
+        if let Some(event) = event {
+            debug!("Send {event:?}");
+            input_events.send(event);
         }
     }
 }
 
 fn keyboard_input(
     mut key_events: EventReader<KeyboardInput>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<InputBindings>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
     mut input_events: EventWriter<InputEvent>,
 ) {
+    let cursor_pos = primary_window.single().cursor_position();
+    let modifiers = Modifiers::pressed(&keyboard);
     for key_event in key_events.read() {
-        match key_event {
-            KeyboardInput {
-                key_code, state, ..
-            } if matches!(key_code, KeyCode::Escape) && state.is_pressed() => {
-                debug!("Send InputEvent::Pause");
-                input_events.send(InputEvent::Pause);
-            }
-            _ => {}
+        let KeyboardInput {
+            key_code, state, ..
+        } = key_event;
+        if !state.is_pressed() || Modifiers::is_modifier(*key_code) {
+            continue;
+        }
+        let binding = Binding {
+            input: PhysicalInput::Key(*key_code),
+            modifiers,
+        };
+        let event = bindings
+            .action(binding)
+            .and_then(|action| action_event(action, cursor_pos.map(ScreenPosition::from)));
+        if let Some(event) = event {
+            debug!("Send {event:?}");
+            input_events.send(event);
         }
     }
 }
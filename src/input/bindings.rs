@@ -0,0 +1,663 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use bevy::input::gamepad::GamepadButtonType;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// Name of the file bindings are saved to, within the configuration directory.
+const BINDINGS_FILE: &str = "controls.txt";
+
+/// Something the player can do, which can be bound to a physical input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    ClearBlock,
+    MarkBlock,
+    ChordBlock,
+    RotateCamera,
+    PanCamera,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    FocusBlock,
+    NextFrontier,
+    ViewPosX,
+    ViewNegX,
+    ViewPosY,
+    ViewNegY,
+    ViewPosZ,
+    ViewNegZ,
+    ViewPosXPosYPosZ,
+    ViewNegXPosYPosZ,
+    ViewPosXPosYNegZ,
+    ViewNegXPosYNegZ,
+    ViewPosXNegYPosZ,
+    ViewNegXNegYPosZ,
+    ViewPosXNegYNegZ,
+    ViewNegXNegYNegZ,
+    ToggleProjection,
+    ZoomCamera,
+    SpreadBlocks,
+    Pause,
+    CursorLeft,
+    CursorRight,
+    CursorUp,
+    CursorDown,
+    CursorAway,
+    CursorToward,
+    ClearSelected,
+    MarkSelected,
+    ChordSelected,
+    CycleSliceAxis,
+    SliceUp,
+    SliceDown,
+    FlipSlice,
+    ToggleLayerPanel,
+    SkipEffects,
+}
+impl Action {
+    pub const ALL: [Action; 44] = [
+        Self::ClearBlock,
+        Self::MarkBlock,
+        Self::ChordBlock,
+        Self::RotateCamera,
+        Self::PanCamera,
+        Self::PanLeft,
+        Self::PanRight,
+        Self::PanUp,
+        Self::PanDown,
+        Self::FocusBlock,
+        Self::NextFrontier,
+        Self::ViewPosX,
+        Self::ViewNegX,
+        Self::ViewPosY,
+        Self::ViewNegY,
+        Self::ViewPosZ,
+        Self::ViewNegZ,
+        Self::ViewPosXPosYPosZ,
+        Self::ViewNegXPosYPosZ,
+        Self::ViewPosXPosYNegZ,
+        Self::ViewNegXPosYNegZ,
+        Self::ViewPosXNegYPosZ,
+        Self::ViewNegXNegYPosZ,
+        Self::ViewPosXNegYNegZ,
+        Self::ViewNegXNegYNegZ,
+        Self::ToggleProjection,
+        Self::ZoomCamera,
+        Self::SpreadBlocks,
+        Self::Pause,
+        Self::CursorLeft,
+        Self::CursorRight,
+        Self::CursorUp,
+        Self::CursorDown,
+        Self::CursorAway,
+        Self::CursorToward,
+        Self::ClearSelected,
+        Self::MarkSelected,
+        Self::ChordSelected,
+        Self::CycleSliceAxis,
+        Self::SliceUp,
+        Self::SliceDown,
+        Self::FlipSlice,
+        Self::ToggleLayerPanel,
+        Self::SkipEffects,
+    ];
+    /// Human readable name of this action.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ClearBlock => "Clear block",
+            Self::MarkBlock => "Mark block",
+            Self::ChordBlock => "Chord block",
+            Self::RotateCamera => "Rotate camera (hold)",
+            Self::PanCamera => "Pan camera (hold)",
+            Self::PanLeft => "Pan left",
+            Self::PanRight => "Pan right",
+            Self::PanUp => "Pan up",
+            Self::PanDown => "Pan down",
+            Self::FocusBlock => "Focus on block",
+            Self::NextFrontier => "Next frontier block",
+            Self::ViewPosX => "View from +X",
+            Self::ViewNegX => "View from -X",
+            Self::ViewPosY => "View from +Y",
+            Self::ViewNegY => "View from -Y",
+            Self::ViewPosZ => "View from +Z",
+            Self::ViewNegZ => "View from -Z",
+            Self::ViewPosXPosYPosZ => "View from +X +Y +Z",
+            Self::ViewNegXPosYPosZ => "View from -X +Y +Z",
+            Self::ViewPosXPosYNegZ => "View from +X +Y -Z",
+            Self::ViewNegXPosYNegZ => "View from -X +Y -Z",
+            Self::ViewPosXNegYPosZ => "View from +X -Y +Z",
+            Self::ViewNegXNegYPosZ => "View from -X -Y +Z",
+            Self::ViewPosXNegYNegZ => "View from +X -Y -Z",
+            Self::ViewNegXNegYNegZ => "View from -X -Y -Z",
+            Self::ToggleProjection => "Orthographic view",
+            Self::ZoomCamera => "Zoom camera",
+            Self::SpreadBlocks => "Spread blocks",
+            Self::Pause => "Pause",
+            Self::CursorLeft => "Cursor left",
+            Self::CursorRight => "Cursor right",
+            Self::CursorUp => "Cursor up",
+            Self::CursorDown => "Cursor down",
+            Self::CursorAway => "Cursor away",
+            Self::CursorToward => "Cursor toward",
+            Self::ClearSelected => "Clear at cursor",
+            Self::MarkSelected => "Mark at cursor",
+            Self::ChordSelected => "Chord at cursor",
+            Self::CycleSliceAxis => "Slice axis",
+            Self::SliceUp => "Slice up",
+            Self::SliceDown => "Slice down",
+            Self::FlipSlice => "Flip slice",
+            Self::ToggleLayerPanel => "Layer panel",
+            Self::SkipEffects => "Skip effects",
+        }
+    }
+    /// Whether this action is driven by the mouse wheel, rather than a button.
+    pub fn is_scroll(&self) -> bool {
+        matches!(self, Self::ZoomCamera | Self::SpreadBlocks)
+    }
+    fn default_binding(&self) -> Binding {
+        use PhysicalInput::*;
+        let (input, modifiers) = match self {
+            Self::ClearBlock => (Mouse(MouseButton::Left), Modifiers::NONE),
+            Self::MarkBlock => (Mouse(MouseButton::Right), Modifiers::NONE),
+            Self::ChordBlock => (Mouse(MouseButton::Left), Modifiers::SHIFT),
+            Self::RotateCamera => (Mouse(MouseButton::Middle), Modifiers::NONE),
+            Self::PanCamera => (Mouse(MouseButton::Middle), Modifiers::SHIFT),
+            Self::PanLeft => (Key(KeyCode::ArrowLeft), Modifiers::NONE),
+            Self::PanRight => (Key(KeyCode::ArrowRight), Modifiers::NONE),
+            Self::PanUp => (Key(KeyCode::ArrowUp), Modifiers::NONE),
+            Self::PanDown => (Key(KeyCode::ArrowDown), Modifiers::NONE),
+            Self::FocusBlock => (Key(KeyCode::KeyG), Modifiers::NONE),
+            Self::NextFrontier => (Key(KeyCode::KeyN), Modifiers::NONE),
+            Self::ViewPosX => (Key(KeyCode::Numpad3), Modifiers::NONE),
+            Self::ViewNegX => (Key(KeyCode::Numpad3), Modifiers::CTRL),
+            Self::ViewPosY => (Key(KeyCode::Numpad7), Modifiers::NONE),
+            Self::ViewNegY => (Key(KeyCode::Numpad7), Modifiers::CTRL),
+            Self::ViewPosZ => (Key(KeyCode::Numpad1), Modifiers::NONE),
+            Self::ViewNegZ => (Key(KeyCode::Numpad1), Modifiers::CTRL),
+            Self::ViewPosXPosYPosZ => (Key(KeyCode::Numpad9), Modifiers::NONE),
+            Self::ViewNegXPosYPosZ => (Key(KeyCode::Numpad8), Modifiers::NONE),
+            Self::ViewPosXPosYNegZ => (Key(KeyCode::Numpad6), Modifiers::NONE),
+            Self::ViewNegXPosYNegZ => (Key(KeyCode::Numpad4), Modifiers::NONE),
+            Self::ViewPosXNegYPosZ => (Key(KeyCode::Numpad9), Modifiers::CTRL),
+            Self::ViewNegXNegYPosZ => (Key(KeyCode::Numpad8), Modifiers::CTRL),
+            Self::ViewPosXNegYNegZ => (Key(KeyCode::Numpad6), Modifiers::CTRL),
+            Self::ViewNegXNegYNegZ => (Key(KeyCode::Numpad4), Modifiers::CTRL),
+            Self::ToggleProjection => (Key(KeyCode::KeyO), Modifiers::NONE),
+            Self::ZoomCamera => (Wheel, Modifiers::NONE),
+            Self::SpreadBlocks => (Wheel, Modifiers::CTRL),
+            Self::Pause => (Key(KeyCode::Escape), Modifiers::NONE),
+            Self::CursorLeft => (Key(KeyCode::KeyA), Modifiers::NONE),
+            Self::CursorRight => (Key(KeyCode::KeyD), Modifiers::NONE),
+            Self::CursorUp => (Key(KeyCode::KeyW), Modifiers::NONE),
+            Self::CursorDown => (Key(KeyCode::KeyS), Modifiers::NONE),
+            Self::CursorAway => (Key(KeyCode::KeyQ), Modifiers::NONE),
+            Self::CursorToward => (Key(KeyCode::KeyE), Modifiers::NONE),
+            Self::ClearSelected => (Key(KeyCode::Space), Modifiers::NONE),
+            Self::MarkSelected => (Key(KeyCode::KeyF), Modifiers::NONE),
+            Self::ChordSelected => (Key(KeyCode::KeyC), Modifiers::NONE),
+            Self::CycleSliceAxis => (Key(KeyCode::KeyX), Modifiers::NONE),
+            Self::SliceUp => (Key(KeyCode::PageUp), Modifiers::NONE),
+            Self::SliceDown => (Key(KeyCode::PageDown), Modifiers::NONE),
+            Self::FlipSlice => (Key(KeyCode::KeyV), Modifiers::NONE),
+            Self::ToggleLayerPanel => (Key(KeyCode::KeyL), Modifiers::NONE),
+            Self::SkipEffects => (Key(KeyCode::Enter), Modifiers::NONE),
+        };
+        Binding { input, modifiers }
+    }
+    /// Gamepad button bound by default. Actions on blocks use the reticle at the center
+    /// of the screen.
+    fn default_gamepad_button(&self) -> Option<GamepadButtonType> {
+        use GamepadButtonType::*;
+        match self {
+            Self::ClearBlock => Some(South),
+            Self::MarkBlock => Some(East),
+            Self::ChordBlock => Some(West),
+            Self::CycleSliceAxis => Some(North),
+            Self::Pause => Some(Start),
+            Self::CursorLeft => Some(DPadLeft),
+            Self::CursorRight => Some(DPadRight),
+            Self::CursorUp => Some(DPadUp),
+            Self::CursorDown => Some(DPadDown),
+            Self::CursorAway => Some(LeftTrigger),
+            Self::CursorToward => Some(RightTrigger),
+            Self::ClearSelected => Some(RightTrigger2),
+            Self::MarkSelected => Some(LeftTrigger2),
+            _ => None,
+        }
+    }
+}
+
+/// A key, mouse button or the mouse wheel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhysicalInput {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Wheel,
+}
+impl Display for PhysicalInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Key(key) => {
+                let name = format!("{key:?}");
+                let name = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                write!(f, "{name}")
+            }
+            Self::Mouse(MouseButton::Other(n)) => write!(f, "Mouse {n}"),
+            Self::Mouse(button) => write!(f, "{button:?} Click"),
+            Self::Wheel => write!(f, "Wheel"),
+        }
+    }
+}
+
+/// Modifier keys that must be held for a [Binding] to apply.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+impl Modifiers {
+    pub const NONE: Self = Self {
+        ctrl: false,
+        shift: false,
+        alt: false,
+    };
+    pub const CTRL: Self = Self {
+        ctrl: true,
+        shift: false,
+        alt: false,
+    };
+    pub const SHIFT: Self = Self {
+        ctrl: false,
+        shift: true,
+        alt: false,
+    };
+    /// Get the modifier keys currently held.
+    pub fn pressed(keys: &ButtonInput<KeyCode>) -> Self {
+        Self {
+            ctrl: keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+            shift: keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+            alt: keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]),
+        }
+    }
+    /// Whether `key` is itself a modifier key, which cannot be bound on its own.
+    pub fn is_modifier(key: KeyCode) -> bool {
+        matches!(
+            key,
+            KeyCode::ControlLeft
+                | KeyCode::ControlRight
+                | KeyCode::ShiftLeft
+                | KeyCode::ShiftRight
+                | KeyCode::AltLeft
+                | KeyCode::AltRight
+        )
+    }
+}
+
+/// A physical input, with the modifier keys held along with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub input: PhysicalInput,
+    pub modifiers: Modifiers,
+}
+impl Binding {
+    /// Text representation used when saving bindings.
+    fn to_config(self) -> String {
+        let mut parts = vec![];
+        if self.modifiers.ctrl {
+            parts.push("Ctrl".to_string());
+        }
+        if self.modifiers.shift {
+            parts.push("Shift".to_string());
+        }
+        if self.modifiers.alt {
+            parts.push("Alt".to_string());
+        }
+        parts.push(match self.input {
+            PhysicalInput::Key(key) => format!("Key:{key:?}"),
+            PhysicalInput::Mouse(MouseButton::Other(n)) => format!("Mouse:{n}"),
+            PhysicalInput::Mouse(button) => format!("Mouse:{button:?}"),
+            PhysicalInput::Wheel => "Wheel".to_string(),
+        });
+        parts.join("+")
+    }
+    /// Parse the representation created by [Binding::to_config].
+    fn from_config(s: &str) -> Option<Self> {
+        let mut parts: Vec<_> = s.split('+').map(str::trim).collect();
+        let last = parts.pop()?;
+        let input = match last.split_once(':') {
+            None if last == "Wheel" => PhysicalInput::Wheel,
+            None => return None,
+            Some(("Key", name)) => PhysicalInput::Key(
+                *BINDABLE_KEYS
+                    .iter()
+                    .find(|key| format!("{key:?}") == name)?,
+            ),
+            Some(("Mouse", name)) => PhysicalInput::Mouse(match name {
+                "Left" => MouseButton::Left,
+                "Right" => MouseButton::Right,
+                "Middle" => MouseButton::Middle,
+                "Back" => MouseButton::Back,
+                "Forward" => MouseButton::Forward,
+                n => MouseButton::Other(n.parse().ok()?),
+            }),
+            Some(_) => return None,
+        };
+        let mut modifiers = Modifiers::NONE;
+        for part in parts {
+            match part {
+                "Ctrl" => modifiers.ctrl = true,
+                "Shift" => modifiers.shift = true,
+                "Alt" => modifiers.alt = true,
+                _ => return None,
+            }
+        }
+        Some(Self { input, modifiers })
+    }
+}
+impl Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+        write!(f, "{}", self.input)
+    }
+}
+
+/// Maps each [Action] to the physical input that triggers it.
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct InputBindings {
+    bindings: HashMap<Action, Binding>,
+    /// Gamepad buttons, which are bound separately so an action can have both.
+    gamepad: HashMap<Action, GamepadButtonType>,
+}
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| (*action, action.default_binding()))
+                .collect(),
+            gamepad: Action::ALL
+                .iter()
+                .filter_map(|action| Some((*action, action.default_gamepad_button()?)))
+                .collect(),
+        }
+    }
+}
+impl InputBindings {
+    pub fn get(&self, action: Action) -> Binding {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_binding())
+    }
+    /// Bind `action` to `binding`, if it is a kind of input the action can use.
+    /// Returns true if the binding was changed.
+    pub fn set(&mut self, action: Action, binding: Binding) -> bool {
+        let is_wheel = binding.input == PhysicalInput::Wheel;
+        if action.is_scroll() != is_wheel {
+            return false;
+        }
+        self.bindings.insert(action, binding);
+        true
+    }
+    /// Find the action bound to exactly `binding`.
+    pub fn action(&self, binding: Binding) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| self.get(*action) == binding)
+    }
+    pub fn gamepad_button(&self, action: Action) -> Option<GamepadButtonType> {
+        self.gamepad.get(&action).copied()
+    }
+    /// Bind `action` to a gamepad button, if the action can use a button.
+    /// Returns true if the binding was changed.
+    pub fn set_gamepad_button(&mut self, action: Action, button: GamepadButtonType) -> bool {
+        if action.is_scroll() {
+            return false;
+        }
+        self.gamepad.insert(action, button);
+        true
+    }
+    /// Find the action bound to the gamepad `button`.
+    pub fn gamepad_action(&self, button: GamepadButtonType) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| self.gamepad_button(*action) == Some(button))
+    }
+    /// Whether the input for `action` is currently held down, with its modifiers.
+    pub fn held(
+        &self,
+        action: Action,
+        keys: &ButtonInput<KeyCode>,
+        mouse_buttons: &ButtonInput<MouseButton>,
+    ) -> bool {
+        let binding = self.get(action);
+        let pressed = match binding.input {
+            PhysicalInput::Key(key) => keys.pressed(key),
+            PhysicalInput::Mouse(button) => mouse_buttons.pressed(button),
+            PhysicalInput::Wheel => false,
+        };
+        pressed && binding.modifiers == Modifiers::pressed(keys)
+    }
+    /// Pairs of actions bound to the same input.
+    pub fn conflicts(&self) -> Vec<(Action, Action)> {
+        let mut conflicts = vec![];
+        for (n, a) in Action::ALL.iter().enumerate() {
+            for b in &Action::ALL[n + 1..] {
+                if self.get(*a) == self.get(*b) {
+                    conflicts.push((*a, *b));
+                }
+            }
+        }
+        conflicts
+    }
+    /// Pairs of actions bound to the same gamepad button.
+    pub fn gamepad_conflicts(&self) -> Vec<(Action, Action)> {
+        let mut conflicts = vec![];
+        for (n, a) in Action::ALL.iter().enumerate() {
+            for b in &Action::ALL[n + 1..] {
+                let button = self.gamepad_button(*a);
+                if button.is_some() && button == self.gamepad_button(*b) {
+                    conflicts.push((*a, *b));
+                }
+            }
+        }
+        conflicts
+    }
+    /// Load saved bindings, falling back to defaults for anything missing or invalid.
+    pub fn load() -> Self {
+        let mut bindings = Self::default();
+        let Some(path) = bindings_path() else {
+            return bindings;
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return bindings,
+            Err(err) => {
+                warn!("Unable to read {}: {err}", path.display());
+                return bindings;
+            }
+        };
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let parsed = line.split_once('=').and_then(|(action, binding)| {
+                let action = Action::ALL
+                    .into_iter()
+                    .find(|a| format!("{a:?}") == action.trim())?;
+                Some((action, binding.trim()))
+            });
+            let valid = match parsed {
+                Some((action, binding)) => match binding.strip_prefix("Gamepad:") {
+                    Some(name) => BINDABLE_BUTTONS
+                        .iter()
+                        .find(|button| format!("{button:?}") == name)
+                        .is_some_and(|button| bindings.set_gamepad_button(action, *button)),
+                    None => Binding::from_config(binding)
+                        .is_some_and(|binding| bindings.set(action, binding)),
+                },
+                None => false,
+            };
+            if !valid {
+                warn!("Ignoring invalid binding {line:?} in {}", path.display());
+            }
+        }
+        info!("Loaded controls from {}", path.display());
+        bindings
+    }
+    /// Save bindings so they are used the next time the game is started.
+    pub fn save(&self) {
+        let Some(path) = bindings_path() else {
+            warn!("No configuration directory, controls will not be saved");
+            return;
+        };
+        let mut contents: String = Action::ALL
+            .iter()
+            .map(|action| format!("{action:?} = {}\n", self.get(*action).to_config()))
+            .collect();
+        for action in Action::ALL {
+            if let Some(button) = self.gamepad_button(action) {
+                contents.push_str(&format!("{action:?} = Gamepad:{button:?}\n"));
+            }
+        }
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, contents));
+        match result {
+            Ok(()) => info!("Saved controls to {}", path.display()),
+            Err(err) => error!("Unable to save controls to {}: {err}", path.display()),
+        }
+    }
+}
+
+/// Location of the saved bindings, in the platform's configuration directory.
+fn bindings_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| std::env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("sweeper-3d").join(BINDINGS_FILE))
+}
+
+/// Keys which can be bound to an action.
+pub const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Backslash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::Backquote,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+];
+
+/// Gamepad buttons which can be bound to an action.
+pub const BINDABLE_BUTTONS: &[GamepadButtonType] = &[
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::C,
+    GamepadButtonType::Z,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::Select,
+    GamepadButtonType::Start,
+    GamepadButtonType::Mode,
+    GamepadButtonType::LeftThumb,
+    GamepadButtonType::RightThumb,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
@@ -0,0 +1,154 @@
+use bevy::input::gamepad::GamepadAxisType;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use super::{action_event, InputBindings, InputEvent, ScreenPosition};
+
+/// Stick deflection below which input is ignored, to avoid drift.
+const STICK_DEAD_ZONE: f32 = 0.15;
+/// Fraction of a full camera turn per second with the stick fully deflected.
+const ROTATE_SPEED: f32 = 0.4;
+/// Lines scrolled per second with the stick fully deflected.
+const ZOOM_SPEED: f32 = 8.0;
+
+/// Handle input from every connected gamepad. The left stick rotates the camera,
+/// and the right stick zooms. Buttons trigger the actions they are bound to, with
+/// actions on blocks using the reticle at the center of the screen.
+pub(super) fn gamepad_input(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    bindings: Res<InputBindings>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    mut input_events: EventWriter<InputEvent>,
+) {
+    let window = primary_window.single();
+    let reticle = ScreenPosition::new(Vec2::new(window.width(), window.height()) / 2.0);
+    let stick = |gamepad, x, y| {
+        let axis = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        let value = Vec2::new(axis(x), axis(y));
+        if value.length() > STICK_DEAD_ZONE {
+            value
+        } else {
+            Vec2::ZERO
+        }
+    };
+    let mut events = vec![];
+    for gamepad in gamepads.iter() {
+        let rotate = stick(
+            gamepad,
+            GamepadAxisType::LeftStickX,
+            GamepadAxisType::LeftStickY,
+        );
+        if rotate != Vec2::ZERO {
+            // Pushing up tilts the camera up, like dragging the mouse down
+            let delta = Vec2::new(rotate.x, -rotate.y) * ROTATE_SPEED * time.delta_seconds();
+            events.push(InputEvent::RotateCamera { delta });
+        }
+        let zoom = stick(
+            gamepad,
+            GamepadAxisType::RightStickX,
+            GamepadAxisType::RightStickY,
+        );
+        if zoom.y != 0.0 {
+            let delta = zoom.y * ZOOM_SPEED * time.delta_seconds();
+            events.push(InputEvent::ZoomCamera { delta });
+        }
+        let pressed = buttons
+            .get_just_pressed()
+            .filter(|button| button.gamepad == gamepad)
+            .filter_map(|button| bindings.gamepad_action(button.button_type));
+        for action in pressed {
+            events.extend(action_event(action, Some(reticle)));
+        }
+    }
+    if !events.is_empty() {
+        // Blocks are hovered and selected through the reticle while the gamepad is in use
+        input_events.send(InputEvent::PointAt(Some(reticle)));
+        for event in events {
+            debug!("Send {event:?}");
+            input_events.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::gamepad::{
+        GamepadButtonChangedEvent, GamepadButtonType, GamepadConnection, GamepadConnectionEvent,
+        GamepadEvent, GamepadInfo,
+    };
+    use bevy::input::InputSystem;
+
+    use super::*;
+    use crate::Action;
+
+    fn test_app(bindings: InputBindings) -> App {
+        let mut app = App::new();
+        app.add_plugins(bevy::input::InputPlugin)
+            .init_resource::<Time>()
+            .insert_resource(bindings)
+            .add_event::<InputEvent>()
+            .add_systems(PreUpdate, gamepad_input.after(InputSystem));
+        app.world.spawn((Window::default(), PrimaryWindow));
+        app.world
+            .send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+                Gamepad::new(0),
+                GamepadConnection::Connected(GamepadInfo {
+                    name: "Test gamepad".to_string(),
+                }),
+            )));
+        app.update();
+        app
+    }
+
+    /// Press `button` on the test gamepad, and get the events sent in response.
+    fn press(app: &mut App, button: GamepadButtonType) -> Vec<String> {
+        app.world
+            .send_event(GamepadEvent::Button(GamepadButtonChangedEvent::new(
+                Gamepad::new(0),
+                button,
+                1.0,
+            )));
+        app.update();
+        let events = app.world.resource::<Events<InputEvent>>();
+        events
+            .get_reader()
+            .read(events)
+            .map(|event| format!("{event:?}"))
+            .collect()
+    }
+
+    #[test]
+    fn default_button_clears_at_reticle() {
+        let mut app = test_app(InputBindings::default());
+        let events = press(&mut app, GamepadButtonType::South);
+        assert_eq!(events.len(), 2, "{events:?}");
+        assert!(events[0].starts_with("PointAt(Some("), "{events:?}");
+        assert!(events[1].starts_with("ClearBlock("), "{events:?}");
+        let window = Window::default();
+        let center = format!("{:?}", Vec2::new(window.width(), window.height()) / 2.0);
+        assert!(events[1].contains(&center), "{events:?}");
+    }
+
+    #[test]
+    fn rebound_button_triggers_new_action() {
+        let mut bindings = InputBindings::default();
+        bindings.set_gamepad_button(Action::MarkBlock, GamepadButtonType::South);
+        bindings.set_gamepad_button(Action::ClearBlock, GamepadButtonType::North);
+        let mut app = test_app(bindings);
+        let events = press(&mut app, GamepadButtonType::South);
+        assert!(events[1].starts_with("MarkBlock("), "{events:?}");
+    }
+
+    #[test]
+    fn unbound_button_sends_nothing() {
+        let mut app = test_app(InputBindings::default());
+        let events = press(&mut app, GamepadButtonType::Mode);
+        assert!(events.is_empty(), "{events:?}");
+    }
+}
@@ -0,0 +1,227 @@
+use bevy::input::touch::Touch;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
+
+use super::{pixels_to_lines, InputEvent, ScreenPosition};
+
+/// Distance in logical pixels a touch can move and still count as a tap.
+const TAP_DISTANCE: f32 = 10.0;
+/// Seconds a touch must be held in place to count as a long press.
+const LONG_PRESS_SECS: f32 = 0.5;
+
+/// Progress of a single touch, from when it starts until it ends.
+#[derive(Debug)]
+pub(super) struct Gesture {
+    /// Seconds since startup when the touch started.
+    started: f32,
+    /// Whether the touch has moved too far to be a tap or long press.
+    moved: bool,
+    /// Whether the touch has already been used, and should do nothing when released.
+    consumed: bool,
+}
+impl Gesture {
+    fn new(started: f32, consumed: bool) -> Self {
+        Self {
+            started,
+            moved: false,
+            consumed,
+        }
+    }
+    /// Decide what a touch held on its own does, given how far it is from where it started.
+    fn hold(&mut self, distance: f32, now: f32) -> Hold {
+        if distance > TAP_DISTANCE {
+            self.moved = true;
+        }
+        if self.moved {
+            Hold::Drag
+        } else if now - self.started >= LONG_PRESS_SECS {
+            self.consumed = true;
+            Hold::LongPress
+        } else {
+            Hold::Wait
+        }
+    }
+    /// Another finger touched down, making this part of a two finger drag or pinch.
+    fn join(&mut self) {
+        self.moved = true;
+    }
+    /// Whether releasing the touch, at `distance` from where it started, is a tap.
+    fn is_tap(&self, distance: f32) -> bool {
+        !self.consumed && !self.moved && distance <= TAP_DISTANCE
+    }
+}
+
+/// What a single touch held down does this frame.
+#[derive(Debug, PartialEq)]
+enum Hold {
+    /// Not yet a long press, and might still be a tap.
+    Wait,
+    /// Moved too far for a tap, so it rotates the camera.
+    Drag,
+    /// Held in place long enough to mark a block.
+    LongPress,
+}
+
+/// Handle touch input. A tap clears a block, a long press marks it, dragging with one
+/// or two fingers rotates the camera, and pinching zooms.
+pub(super) fn touch_input(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    mut contexts: EguiContexts,
+    mut input_events: EventWriter<InputEvent>,
+    mut gestures: Local<HashMap<u64, Gesture>>,
+) {
+    let window = primary_window.single();
+    let window_size = Vec2::new(window.width(), window.height());
+    let now = time.elapsed_seconds();
+    let over_ui = contexts.ctx_mut().is_pointer_over_area();
+    for touch in touches.iter_just_pressed() {
+        // Touches on a menu or HUD element are meant for that element
+        gestures.insert(touch.id(), Gesture::new(now, over_ui));
+    }
+    let active: Vec<&Touch> = touches
+        .iter()
+        .filter(|touch| gestures.get(&touch.id()).is_some_and(|g| !g.consumed))
+        .collect();
+    match active[..] {
+        [touch] => {
+            let gesture = gestures.get_mut(&touch.id()).expect("active touch");
+            match gesture.hold(touch.distance().length(), now) {
+                Hold::Drag => send_rotation(touch.delta(), window_size, &mut input_events),
+                Hold::LongPress => {
+                    debug!("Send InputEvent::MarkBlock");
+                    let position = screen_position(touch.position(), window_size);
+                    input_events.send(InputEvent::MarkBlock(position));
+                }
+                Hold::Wait => {}
+            }
+        }
+        [a, b] => {
+            // Neither touch is a tap once a second finger is down
+            for touch in [a, b] {
+                if let Some(gesture) = gestures.get_mut(&touch.id()) {
+                    gesture.join();
+                }
+            }
+            let delta = (a.delta() + b.delta()) / 2.0;
+            send_rotation(delta, window_size, &mut input_events);
+            let delta = pinch_zoom(
+                [a.previous_position(), b.previous_position()],
+                [a.position(), b.position()],
+            );
+            if delta != 0.0 {
+                debug!("Send InputEvent::ZoomCamera");
+                input_events.send(InputEvent::ZoomCamera { delta });
+            }
+        }
+        _ => {}
+    }
+    for touch in touches.iter_just_released() {
+        let Some(gesture) = gestures.remove(&touch.id()) else {
+            continue;
+        };
+        if gesture.is_tap(touch.distance().length()) {
+            debug!("Send InputEvent::ClearBlock");
+            let position = screen_position(touch.position(), window_size);
+            input_events.send(InputEvent::ClearBlock(position));
+        }
+    }
+    for touch in touches.iter_just_canceled() {
+        gestures.remove(&touch.id());
+    }
+}
+
+/// Zoom for two touches moving from `previous` to `current` positions, in the same lines
+/// as the mouse wheel. Spreading the touches apart zooms in, like scrolling up.
+fn pinch_zoom(previous: [Vec2; 2], current: [Vec2; 2]) -> f32 {
+    let spread = current[0].distance(current[1]);
+    let previous_spread = previous[0].distance(previous[1]);
+    pixels_to_lines(spread - previous_spread)
+}
+
+/// Position of a touch within the window. Touches can be reported slightly outside the
+/// window, so they are clamped to its edges.
+fn screen_position(position: Vec2, window_size: Vec2) -> ScreenPosition {
+    ScreenPosition::new(position.clamp(Vec2::ZERO, window_size.max(Vec2::ZERO)))
+}
+
+fn send_rotation(delta: Vec2, window_size: Vec2, input_events: &mut EventWriter<InputEvent>) {
+    if delta.length_squared() > 0.0 {
+        // Scale based on window size
+        let delta = delta / window_size;
+        debug!("Send InputEvent::RotateCamera");
+        input_events.send(InputEvent::RotateCamera { delta });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quick_release_in_place_is_tap() {
+        let mut gesture = Gesture::new(0.0, false);
+        assert_eq!(
+            gesture.hold(TAP_DISTANCE, LONG_PRESS_SECS / 2.0),
+            Hold::Wait
+        );
+        assert!(gesture.is_tap(TAP_DISTANCE));
+        assert!(!gesture.is_tap(TAP_DISTANCE + 1.0));
+    }
+
+    #[test]
+    fn holding_in_place_is_long_press() {
+        let mut gesture = Gesture::new(1.0, false);
+        assert_eq!(gesture.hold(0.0, 1.0 + LONG_PRESS_SECS), Hold::LongPress);
+        // The long press is used up, so releasing it does not also clear
+        assert!(!gesture.is_tap(0.0));
+    }
+
+    #[test]
+    fn moving_away_is_drag() {
+        let mut gesture = Gesture::new(0.0, false);
+        assert_eq!(gesture.hold(TAP_DISTANCE + 1.0, 0.1), Hold::Drag);
+        // Still a drag after moving back, and after the long press delay
+        assert_eq!(gesture.hold(0.0, LONG_PRESS_SECS * 2.0), Hold::Drag);
+        assert!(!gesture.is_tap(0.0));
+    }
+
+    #[test]
+    fn second_finger_turns_taps_into_drag() {
+        let mut gesture = Gesture::new(0.0, false);
+        gesture.join();
+        assert_eq!(gesture.hold(0.0, 0.1), Hold::Drag);
+        assert!(!gesture.is_tap(0.0));
+    }
+
+    #[test]
+    fn touch_on_ui_does_nothing() {
+        let gesture = Gesture::new(0.0, true);
+        assert!(!gesture.is_tap(0.0));
+    }
+
+    #[test]
+    fn pinch_matches_mouse_wheel() {
+        let previous = [Vec2::new(100.0, 100.0), Vec2::new(200.0, 100.0)];
+        let apart = [Vec2::new(90.0, 100.0), Vec2::new(210.0, 100.0)];
+        let zoom_in = pinch_zoom(previous, apart);
+        assert_eq!(zoom_in, pixels_to_lines(20.0));
+        assert!(zoom_in > 0.0);
+        assert_eq!(pinch_zoom(apart, previous), -zoom_in);
+        // Moving both touches together is a drag, not a pinch
+        let moved = previous.map(|p| p + Vec2::new(30.0, -5.0));
+        assert_eq!(pinch_zoom(previous, moved), 0.0);
+    }
+
+    #[test]
+    fn positions_are_clamped_to_window() {
+        let window_size = Vec2::new(800.0, 600.0);
+        let clamp = |position| *screen_position(position, window_size);
+        assert_eq!(clamp(Vec2::new(-3.0, 50.0)), Vec2::new(0.0, 50.0));
+        assert_eq!(clamp(Vec2::new(801.0, -0.5)), Vec2::new(800.0, 0.0));
+        assert_eq!(clamp(Vec2::new(400.0, 300.0)), Vec2::new(400.0, 300.0));
+    }
+}
@@ -1,14 +1,16 @@
 use bevy::prelude::*;
 
+mod board_code;
 mod game;
 mod input;
 mod loader;
 mod menu;
 mod settings;
 
-pub use input::InputEvent;
+pub use board_code::{BoardCode, CodeError};
+pub use input::{Action, Binding, InputBindings, InputEvent, Modifiers, PhysicalInput};
 pub use loader::GameAssets;
-pub use settings::{FieldSettings, GameSettings, Safety};
+pub use settings::{FieldSettings, GameSettings, Neighborhood, Safety};
 
 pub use game::GamePlugin;
 pub use input::InputPlugin;
@@ -27,6 +29,8 @@ pub enum GameState {
     MenuCustom,
     /// Settings menu
     MenuSettings,
+    /// Controls menu, for changing input bindings
+    MenuControls,
     /// Game has started, but no cell has been clicked yet.
     GameStart,
     /// Game transitions to this state once the first cell is clicked.
@@ -1,18 +1,36 @@
-use bevy::{app::AppExit, prelude::*};
+use bevy::{app::AppExit, input::mouse::MouseWheel, prelude::*, utils::HashMap};
 use bevy_egui::{egui, EguiContexts, EguiPlugin};
 
-use crate::{game::GameResult, FieldSettings, GameSettings, GameState, Safety};
+use crate::{
+    game::{
+        Block, BlockCursor, BlockSpacing, Contains, FieldIndex, FieldSlice, GameResult, GameStats,
+        HoveredBlock, LossAnalysis, MainCamera, Marking, Minefield, Mistakes, SliceSide,
+    },
+    Action, Binding, BoardCode, FieldSettings, GameSettings, GameState, InputBindings, InputEvent,
+    Modifiers, Neighborhood, PhysicalInput, Safety,
+};
 
 pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<LayerPanel>();
         app.add_plugins(EguiPlugin).add_systems(
             Update,
             (
                 display_main_menu.run_if(in_state(GameState::MenuMain)),
                 display_custom_menu.run_if(in_state(GameState::MenuCustom)),
                 display_settings_menu.run_if(in_state(GameState::MenuSettings)),
+                display_controls_menu.run_if(in_state(GameState::MenuControls)),
                 display_game_over.run_if(in_state(GameState::GameOver)),
+                display_hud.run_if(GameState::in_game()),
+                display_reticle.run_if(GameState::in_game()),
+                display_orientation_cube.run_if(GameState::in_game()),
+                // Panels take their space before anchored windows are placed
+                (toggle_layer_panel, display_layer_panel)
+                    .chain()
+                    .before(display_hud)
+                    .before(display_orientation_cube)
+                    .run_if(GameState::in_game()),
             ),
         );
     }
@@ -36,6 +54,7 @@ fn global_settings(ctx: &mut egui::Context) {
     });
     let mut visuals = egui::Visuals::dark();
     visuals.window_shadow = egui::epaint::Shadow::NONE;
+    ctx.set_visuals(visuals);
 }
 
 fn create_menu_window<'a>(title: impl Into<egui::WidgetText>) -> egui::Window<'a> {
@@ -75,6 +94,21 @@ fn display_main_menu(
                         next_state.set(GameState::MenuCustom);
                     }
                 });
+                ui.horizontal_centered(|ui| {
+                    ui.label("Classic:");
+                    if ui.add(egui::Button::new("Beginner")).clicked() {
+                        field_settings.set_if_neq(FieldSettings::beginner());
+                        next_state.set(GameState::GameStart);
+                    }
+                    if ui.add(egui::Button::new("Intermediate")).clicked() {
+                        field_settings.set_if_neq(FieldSettings::intermediate());
+                        next_state.set(GameState::GameStart);
+                    }
+                    if ui.add(egui::Button::new("Expert")).clicked() {
+                        field_settings.set_if_neq(FieldSettings::expert());
+                        next_state.set(GameState::GameStart);
+                    }
+                });
                 if ui.add(egui::Button::new("Settings")).clicked() {
                     next_state.set(GameState::MenuSettings);
                 }
@@ -89,8 +123,15 @@ fn display_main_menu(
 fn display_custom_menu(
     mut contexts: EguiContexts,
     mut field_settings: ResMut<FieldSettings>,
+    mut game_settings: ResMut<GameSettings>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut code_text: Local<String>,
+    mut code_error: Local<Option<String>>,
 ) {
+    let mut load_code = None;
+    let previous = (field_settings.field_size, field_settings.mine_density);
+    // Flat fields can be larger, to fit classic boards
+    let max_size = FieldSettings::max_size(field_settings.field_size);
     let (field_size, mine_density) = field_settings.fields_mut();
     let ctx = contexts.ctx_mut();
     global_settings(ctx);
@@ -99,9 +140,10 @@ fn display_custom_menu(
             ui.vertical_centered(|ui| {
                 ui.horizontal_centered(|ui| {
                     ui.add(egui::Label::new("Size:"));
-                    ui.add(egui::DragValue::new(&mut field_size[0]).clamp_range(1..=20));
-                    ui.add(egui::DragValue::new(&mut field_size[1]).clamp_range(1..=20));
-                    ui.add(egui::DragValue::new(&mut field_size[2]).clamp_range(1..=20));
+                    for size in field_size.iter_mut() {
+                        ui.add(egui::DragValue::new(size).clamp_range(1..=max_size))
+                            .on_hover_text("Set any size to 1 for a classic 2D board.");
+                    }
                 });
                 ui.horizontal_centered(|ui| {
                     ui.add(egui::Label::new("Mine Density:"));
@@ -116,12 +158,49 @@ fn display_custom_menu(
                         next_state.set(GameState::GameStart);
                     }
                     if ui.add(egui::Button::new("Back")).clicked() {
+                        *code_error = None;
                         next_state.set(GameState::MenuMain);
                     }
                 });
+                ui.separator();
+                ui.horizontal_centered(|ui| {
+                    ui.add(egui::Label::new("Enter code:"));
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut *code_text)
+                            .hint_text("XXXX-XXXX-XXXX-XXXX-XXXX-X")
+                            .desired_width(320.0),
+                    );
+                    let submitted =
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if ui.add(egui::Button::new("Play")).clicked() || submitted {
+                        load_code = Some(code_text.parse::<BoardCode>());
+                    }
+                });
+                if let Some(error) = code_error.as_ref() {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
             });
         });
     });
+    // An exact mine count only applies to the board it was chosen for
+    if field_settings.mine_count.is_some()
+        && previous != (field_settings.field_size, field_settings.mine_density)
+    {
+        field_settings.mine_count = None;
+    }
+    match load_code {
+        Some(Ok(code)) => {
+            info!("Starting game from board code {code}");
+            *code_error = None;
+            code.apply(&mut field_settings, &mut game_settings);
+            next_state.set(GameState::GameStart);
+        }
+        Some(Err(err)) => {
+            warn!("Invalid board code {:?}: {err}", *code_text);
+            *code_error = Some(err.to_string());
+        }
+        None => {}
+    }
 }
 
 fn display_settings_menu(
@@ -129,7 +208,23 @@ fn display_settings_menu(
     mut game_settings: ResMut<GameSettings>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    let safety = &mut game_settings.safety;
+    let GameSettings {
+        safety,
+        neighborhood,
+        maybe_marks,
+        protect_maybe_marks,
+        lives,
+        win_by_marking,
+        auto_clear_marked,
+        focus_on_hover,
+        highlight_frontier,
+        camera_smoothing,
+        camera_inertia,
+        orthographic,
+        reveal_duration,
+        reduced_motion,
+        training,
+    } = &mut *game_settings;
     let ctx = contexts.ctx_mut();
     global_settings(ctx);
     create_menu_window("Settings").show(ctx, |ui| {
@@ -152,6 +247,77 @@ fn display_settings_menu(
                         );
                 });
                 ui.horizontal_centered(|ui| {
+                    ui.label("Adjacent Blocks:");
+                    ui.radio_value(neighborhood, Neighborhood::Full, "All")
+                        .on_hover_text("Blocks sharing a face, edge or corner are adjacent.");
+                    ui.radio_value(neighborhood, Neighborhood::Edges, "Edges")
+                        .on_hover_text("Blocks sharing a face or an edge are adjacent.");
+                    ui.radio_value(neighborhood, Neighborhood::Faces, "Faces")
+                        .on_hover_text("Only blocks sharing a face are adjacent.");
+                });
+                ui.horizontal_centered(|ui| {
+                    ui.checkbox(maybe_marks, "Maybe Marks").on_hover_text(
+                        "Marking a block a second time marks it as maybe containing a mine.",
+                    );
+                    ui.add_enabled(
+                        *maybe_marks,
+                        egui::Checkbox::new(protect_maybe_marks, "Protect Maybe-Marked Blocks"),
+                    )
+                    .on_hover_text("Blocks marked as maybe containing a mine cannot be cleared.");
+                });
+                ui.horizontal_centered(|ui| {
+                    ui.label("Lives:");
+                    ui.add(egui::DragValue::new(lives).clamp_range(1..=9))
+                        .on_hover_text("Number of mines that can be cleared before the game is lost.");
+                });
+                ui.horizontal_centered(|ui| {
+                    ui.checkbox(win_by_marking, "Win by Marking").on_hover_text(
+                        "Marking every mine, and nothing else, wins the game.",
+                    );
+                    ui.checkbox(auto_clear_marked, "Auto-Clear").on_hover_text(
+                        "Once every mine is marked, and nothing else, all other blocks are cleared.",
+                    );
+                });
+                ui.horizontal_centered(|ui| {
+                    ui.checkbox(focus_on_hover, "Focus on Hover").on_hover_text(
+                        "Hovering over a number fades out all blocks not adjacent to it.",
+                    );
+                    ui.checkbox(highlight_frontier, "Highlight Frontier").on_hover_text(
+                        "Highlight hidden blocks next to revealed numbers. N cycles through them.",
+                    );
+                });
+                ui.horizontal_centered(|ui| {
+                    ui.add(egui::Slider::new(camera_smoothing, 0.0..=0.5).text("Camera Smoothing"))
+                        .on_hover_text("How gradually the camera moves. 0 moves instantly.");
+                });
+                ui.horizontal_centered(|ui| {
+                    ui.add(egui::Slider::new(camera_inertia, 0.0..=1.0).text("Camera Inertia"))
+                        .on_hover_text("How long the camera keeps spinning after it is rotated.");
+                });
+                ui.horizontal_centered(|ui| {
+                    ui.add(egui::Slider::new(reveal_duration, 0.0..=2.0).text("Reveal Wave"))
+                        .on_hover_text(
+                            "How long openings take to spread out from the cleared block. 0 reveals instantly.",
+                        );
+                    ui.checkbox(reduced_motion, "Reduced Motion").on_hover_text(
+                        "Turn off reveal waves, explosions and victory effects. Enter skips them.",
+                    );
+                });
+                ui.horizontal_centered(|ui| {
+                    ui.checkbox(training, "Training Mode").on_hover_text(concat!(
+                        "Flag wrong marks and safe blocks left next to satisfied numbers ",
+                        "as soon as they happen. Results are tagged as training.",
+                    ));
+                });
+                ui.horizontal_centered(|ui| {
+                    ui.checkbox(orthographic, "Orthographic View").on_hover_text(
+                        "Show the field without perspective, so layers line up when viewed face-on.",
+                    );
+                });
+                ui.horizontal_centered(|ui| {
+                    if ui.add(egui::Button::new("Controls")).clicked() {
+                        next_state.set(GameState::MenuControls);
+                    }
                     if ui.add(egui::Button::new("Back")).clicked() {
                         next_state.set(GameState::MenuMain);
                     }
@@ -161,12 +327,173 @@ fn display_settings_menu(
     });
 }
 
+/// Get the input pressed this frame, to bind to an action.
+fn capture_binding(
+    keys: &ButtonInput<KeyCode>,
+    mouse_buttons: &ButtonInput<MouseButton>,
+    scrolled: bool,
+) -> Option<Binding> {
+    let input = keys
+        .get_just_pressed()
+        .find(|key| !Modifiers::is_modifier(**key))
+        .map(|key| PhysicalInput::Key(*key))
+        .or_else(|| {
+            mouse_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| PhysicalInput::Mouse(*button))
+        })
+        .or(scrolled.then_some(PhysicalInput::Wheel))?;
+    Some(Binding {
+        input,
+        modifiers: Modifiers::pressed(keys),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn display_controls_menu(
+    mut contexts: EguiContexts,
+    mut bindings: ResMut<InputBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut rebinding: Local<Option<Action>>,
+    mut rebinding_gamepad: Local<Option<Action>>,
+) {
+    let scrolled = mouse_wheel_events.read().count() > 0;
+    if let Some(action) = *rebinding {
+        if keys.just_pressed(KeyCode::Escape) {
+            *rebinding = None;
+        } else if let Some(binding) = capture_binding(&keys, &mouse_buttons, scrolled) {
+            // Keep waiting if the input can't be used for this action
+            if bindings.set(action, binding) {
+                debug!("Bind {action:?} to {binding}");
+                *rebinding = None;
+            }
+        }
+    }
+    if let Some(action) = *rebinding_gamepad {
+        if keys.just_pressed(KeyCode::Escape) {
+            *rebinding_gamepad = None;
+        } else if let Some(button) = gamepad_buttons.get_just_pressed().next() {
+            if bindings.set_gamepad_button(action, button.button_type) {
+                debug!("Bind {action:?} to {:?}", button.button_type);
+                *rebinding_gamepad = None;
+            }
+        }
+    }
+    let conflicts = bindings.conflicts();
+    let gamepad_conflicts = bindings.gamepad_conflicts();
+    let ctx = contexts.ctx_mut();
+    global_settings(ctx);
+    create_menu_window("Controls").show(ctx, |ui| {
+        ui.allocate_ui(egui::Vec2::new(0.0, 0.0), |ui| {
+            ui.vertical_centered(|ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("controls").striped(true).show(ui, |ui| {
+                            for action in Action::ALL {
+                                ui.label(action.name());
+                                let text = if *rebinding == Some(action) {
+                                    egui::RichText::new("Press input...").italics()
+                                } else {
+                                    let binding = bindings.get(action).to_string();
+                                    let conflicting =
+                                        conflicts.iter().any(|(a, b)| *a == action || *b == action);
+                                    if conflicting {
+                                        egui::RichText::new(binding).color(egui::Color32::RED)
+                                    } else {
+                                        egui::RichText::new(binding)
+                                    }
+                                };
+                                let hint = if action.is_scroll() {
+                                    "Click, then scroll with any modifier keys held."
+                                } else {
+                                    "Click, then press a key or mouse button. Escape cancels."
+                                };
+                                if ui.button(text).on_hover_text(hint).clicked() {
+                                    *rebinding = Some(action);
+                                    *rebinding_gamepad = None;
+                                }
+                                if action.is_scroll() {
+                                    ui.label("");
+                                } else {
+                                    let text = if *rebinding_gamepad == Some(action) {
+                                        egui::RichText::new("Press button...").italics()
+                                    } else {
+                                        let button = bindings
+                                            .gamepad_button(action)
+                                            .map_or("-".to_string(), |b| format!("{b:?}"));
+                                        let conflicting = gamepad_conflicts
+                                            .iter()
+                                            .any(|(a, b)| *a == action || *b == action);
+                                        if conflicting {
+                                            egui::RichText::new(button).color(egui::Color32::RED)
+                                        } else {
+                                            egui::RichText::new(button)
+                                        }
+                                    };
+                                    let hint =
+                                        "Click, then press a gamepad button. Escape cancels.";
+                                    if ui.button(text).on_hover_text(hint).clicked() {
+                                        *rebinding_gamepad = Some(action);
+                                        *rebinding = None;
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                for (a, b) in &conflicts {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("{} and {} use the same input", a.name(), b.name()),
+                    );
+                }
+                for (a, b) in &gamepad_conflicts {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("{} and {} use the same gamepad button", a.name(), b.name()),
+                    );
+                }
+                ui.horizontal_centered(|ui| {
+                    if ui.add(egui::Button::new("Reset")).clicked() {
+                        *bindings = InputBindings::default();
+                        *rebinding = None;
+                        *rebinding_gamepad = None;
+                    }
+                    if ui
+                        .add_enabled(
+                            conflicts.is_empty() && gamepad_conflicts.is_empty(),
+                            egui::Button::new("Back"),
+                        )
+                        .clicked()
+                    {
+                        bindings.save();
+                        *rebinding = None;
+                        *rebinding_gamepad = None;
+                        next_state.set(GameState::MenuSettings);
+                    }
+                });
+            });
+        });
+    });
+}
+
 fn display_game_over(
     mut contexts: EguiContexts,
     mut next_state: ResMut<NextState<GameState>>,
     mut exit_events: EventWriter<AppExit>,
     game_result: Res<GameResult>,
+    game_stats: Res<GameStats>,
+    loss_analysis: Res<LossAnalysis>,
+    field: Query<&Minefield>,
 ) {
+    let field = field.single();
+    let code = field.board_code().to_string();
     let ctx = contexts.ctx_mut();
     global_settings(ctx);
     egui::Window::new(match *game_result {
@@ -184,6 +511,56 @@ fn display_game_over(
     .show(ctx, |ui| {
         ui.allocate_ui(egui::Vec2::new(0.0, 0.0), |ui| {
             ui.vertical_centered(|ui| {
+                if let Some(analysis) = field.analysis() {
+                    ui.horizontal_centered(|ui| {
+                        ui.label(format!("3BV: {}", analysis.bbbv)).on_hover_text(format!(
+                            "Minimum clicks needed to clear this field: {} openings and {} other blocks.",
+                            analysis.openings,
+                            analysis.island_sizes.iter().sum::<usize>()
+                        ));
+                        ui.label(format!("Clicks: {}", game_stats.clicks));
+                        if matches!(*game_result, GameResult::Victory) && game_stats.clicks > 0 {
+                            let efficiency = analysis.bbbv as f32 / game_stats.clicks as f32;
+                            ui.label(format!("Efficiency: {:.0}%", efficiency * 100.0));
+                        }
+                    });
+                }
+                match &*loss_analysis {
+                    LossAnalysis::None => {}
+                    LossAnalysis::TooComplex => {
+                        ui.label("The field was too complex to analyze the losing click.");
+                    }
+                    LossAnalysis::Analyzed {
+                        clicked,
+                        mine_probability,
+                        safe,
+                    } => {
+                        ui.label(format!(
+                            "Block {} had a {:.0}% chance of being a mine.",
+                            FieldIndex::from(clicked),
+                            mine_probability * 100.0
+                        ));
+                        if safe.is_empty() {
+                            ui.label("The click was forced: no block was certainly safe.");
+                        } else {
+                            ui.label(format!(
+                                "The click was not forced: {} safe blocks were available.",
+                                safe.len()
+                            ))
+                            .on_hover_text("Safe blocks are highlighted in green.");
+                        }
+                    }
+                }
+                if game_stats.training {
+                    ui.label(format!("Training game: {} mistakes", game_stats.mistakes))
+                        .on_hover_text("Training games are not comparable with normal games.");
+                }
+                ui.horizontal_centered(|ui| {
+                    ui.label(format!("Board code: {code}"));
+                    if ui.add(egui::Button::new("Copy")).clicked() {
+                        ui.output_mut(|o| o.copied_text = code.clone());
+                    }
+                });
                 ui.horizontal_centered(|ui| {
                     if ui.add(egui::Button::new("Restart")).clicked() {
                         next_state.set(GameState::GameStart);
@@ -199,3 +576,375 @@ fn display_game_over(
         });
     });
 }
+
+/// Show a reticle at the center of the screen, which selects blocks with a gamepad.
+fn display_reticle(mut contexts: EguiContexts, gamepads: Res<Gamepads>) {
+    if gamepads.iter().next().is_none() {
+        return;
+    }
+    let ctx = contexts.ctx_mut();
+    let center = ctx.screen_rect().center();
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("reticle"),
+    ));
+    let stroke = egui::Stroke::new(2.0, egui::Color32::from_white_alpha(180));
+    painter.circle_stroke(center, 8.0, stroke);
+    painter.circle_filled(center, 1.5, stroke.color);
+}
+
+/// Show a cube in the corner of the screen matching the orientation of the field.
+/// Clicking a face or corner of the cube looks at the field from that side.
+fn display_orientation_cube(
+    mut contexts: EguiContexts,
+    camera: Query<&Transform, With<MainCamera>>,
+    mut input_events: EventWriter<InputEvent>,
+) {
+    let Ok(camera_transform) = camera.get_single() else {
+        return;
+    };
+    let to_view = camera_transform.rotation.inverse();
+    let ctx = contexts.ctx_mut();
+    egui::Area::new(egui::Id::new("orientation_cube"))
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+        .show(ctx, |ui| {
+            let size = 120.0;
+            let (response, painter) =
+                ui.allocate_painter(egui::Vec2::splat(size), egui::Sense::click());
+            let center = response.rect.center();
+            let scale = size / 4.0;
+            // Project a point around the cube onto the screen, and get its depth
+            let project = |point: IVec3| {
+                let view = to_view * point.as_vec3();
+                (center + egui::Vec2::new(view.x, -view.y) * scale, view.z)
+            };
+            let mut targets = vec![];
+            for (name, axis, color) in [
+                ("X", IVec3::X, egui::Color32::from_rgb(160, 60, 60)),
+                ("Y", IVec3::Y, egui::Color32::from_rgb(60, 160, 60)),
+                ("Z", IVec3::Z, egui::Color32::from_rgb(60, 90, 170)),
+            ] {
+                for (normal, sign) in [(axis, "+"), (-axis, "-")] {
+                    let (face_center, depth) = project(normal);
+                    // Only faces towards the camera are visible
+                    if depth <= 0.0 {
+                        continue;
+                    }
+                    let [u, v] = [normal.yzx(), normal.zxy()];
+                    let corners = [u + v, u - v, -u - v, -u + v].map(|c| project(normal + c).0);
+                    let fill = if response.hovered() {
+                        color
+                    } else {
+                        color.gamma_multiply(0.8)
+                    };
+                    painter.add(egui::Shape::convex_polygon(
+                        corners.to_vec(),
+                        fill,
+                        egui::Stroke::new(1.0, egui::Color32::BLACK),
+                    ));
+                    painter.text(
+                        face_center,
+                        egui::Align2::CENTER_CENTER,
+                        format!("{sign}{name}"),
+                        egui::FontId::proportional(14.0),
+                        egui::Color32::WHITE,
+                    );
+                    targets.push((normal, face_center));
+                }
+            }
+            for x in [-1, 1] {
+                for y in [-1, 1] {
+                    for z in [-1, 1] {
+                        let corner = IVec3::new(x, y, z);
+                        let (position, depth) = project(corner);
+                        if depth > 0.0 {
+                            painter.circle_filled(position, 4.0, egui::Color32::LIGHT_GRAY);
+                            targets.push((corner, position));
+                        }
+                    }
+                }
+            }
+            let Some(pointer) = response.interact_pointer_pos() else {
+                return;
+            };
+            if !response.clicked() {
+                return;
+            }
+            // Snap to the nearest face center or corner to the click
+            let nearest = targets
+                .into_iter()
+                .min_by(|(_, a), (_, b)| a.distance(pointer).total_cmp(&b.distance(pointer)));
+            if let Some((direction, _)) = nearest {
+                debug!("Send InputEvent::SnapView");
+                input_events.send(InputEvent::SnapView { direction });
+            }
+        });
+}
+
+/// Heads-up display shown during the game.
+#[allow(clippy::too_many_arguments)]
+fn display_hud(
+    mut contexts: EguiContexts,
+    field_settings: Res<FieldSettings>,
+    mut game_settings: ResMut<GameSettings>,
+    game_stats: Res<GameStats>,
+    mistakes: Res<Mistakes>,
+    mut slice: ResMut<FieldSlice>,
+    mut spacing: ResMut<BlockSpacing>,
+    mut layer_panel: ResMut<LayerPanel>,
+    hovered: Res<HoveredBlock>,
+    cursor: Res<BlockCursor>,
+) {
+    let ctx = contexts.ctx_mut();
+    global_settings(ctx);
+    egui::Window::new("HUD")
+        .title_bar(false)
+        .anchor(egui::Align2::LEFT_TOP, [5.0, 5.0])
+        .collapsible(false)
+        .movable(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if game_settings.lives > 1 {
+                let lives_remaining = game_settings.lives.saturating_sub(game_stats.mines_hit);
+                ui.label(format!("Lives: {lives_remaining}/{}", game_settings.lives));
+            }
+            if game_stats.training {
+                ui.label(format!("Training mistakes: {}", game_stats.mistakes));
+                if let Some(message) = mistakes.message() {
+                    ui.colored_label(egui::Color32::from_rgb(255, 140, 60), message);
+                }
+            }
+            // The keyboard cursor takes precedence once it is in use
+            match cursor.index.or(hovered.target.map(|(_, index)| index)) {
+                Some(index) => ui.label(format!("Block: {}", FieldIndex::from(index))),
+                None => ui.label("Block: -"),
+            };
+            ui.checkbox(&mut layer_panel.visible, "Layer Panel")
+                .on_hover_text("Show the field as a stack of 2D layers (L).");
+            let mut highlight_frontier = game_settings.highlight_frontier;
+            if ui
+                .checkbox(&mut highlight_frontier, "Frontier")
+                .on_hover_text("Highlight hidden blocks next to revealed numbers (N cycles).")
+                .changed()
+            {
+                game_settings.highlight_frontier = highlight_frontier;
+            }
+            let mut target_spacing = spacing.target;
+            ui.add(
+                egui::Slider::new(&mut target_spacing, BlockSpacing::MIN..=BlockSpacing::MAX)
+                    .text("Spacing"),
+            )
+            .on_hover_text("Spread blocks apart to see between them (Ctrl + scroll).");
+            if target_spacing != spacing.target {
+                spacing.target = target_spacing;
+            }
+            ui.horizontal(|ui| {
+                ui.label("Slice:");
+                let axis_name = |axis| match axis {
+                    None => "Off",
+                    Some(0) => "X",
+                    Some(1) => "Y",
+                    Some(_) => "Z",
+                };
+                let mut axis = slice.axis;
+                egui::ComboBox::from_id_source("slice_axis")
+                    .selected_text(axis_name(axis))
+                    .show_ui(ui, |ui| {
+                        for option in [None, Some(0), Some(1), Some(2)] {
+                            ui.selectable_value(&mut axis, option, axis_name(option));
+                        }
+                    })
+                    .response
+                    .on_hover_text("Hide blocks on one side of a plane (X to cycle).");
+                if axis != slice.axis {
+                    slice.set_axis(axis, field_settings.field_size);
+                }
+            });
+            if let Some(axis) = slice.axis {
+                let max_layer = field_settings.field_size[axis] - 1;
+                let mut layer = slice.layer;
+                ui.add(egui::Slider::new(&mut layer, 0..=max_layer).text("Layer"))
+                    .on_hover_text("Move the slicing plane (Page Up/Page Down).");
+                if layer != slice.layer {
+                    slice.layer = layer;
+                }
+                let mut hide_below = slice.hidden_side == SliceSide::Below;
+                if ui
+                    .checkbox(&mut hide_below, "Hide Below")
+                    .on_hover_text("Swap which side of the plane is hidden (V).")
+                    .changed()
+                {
+                    slice.hidden_side = if hide_below {
+                        SliceSide::Below
+                    } else {
+                        SliceSide::Above
+                    };
+                }
+            }
+        });
+}
+
+/// Panel showing the field as a stack of 2D grids, one per layer.
+#[derive(Debug, Resource)]
+struct LayerPanel {
+    visible: bool,
+    /// Axis perpendicular to the layers.
+    axis: usize,
+}
+impl Default for LayerPanel {
+    /// Horizontal layers, seen from above.
+    fn default() -> Self {
+        Self {
+            visible: false,
+            axis: 1,
+        }
+    }
+}
+
+fn toggle_layer_panel(
+    mut input_events: EventReader<InputEvent>,
+    mut layer_panel: ResMut<LayerPanel>,
+) {
+    for input_event in input_events.read() {
+        if let InputEvent::ToggleLayerPanel = input_event {
+            layer_panel.visible = !layer_panel.visible;
+        }
+    }
+}
+
+/// Text color for each number of adjacent mines, based on classic minesweeper.
+fn number_color(adjacent_mines: u8) -> egui::Color32 {
+    match adjacent_mines {
+        1 => egui::Color32::from_rgb(100, 150, 255),
+        2 => egui::Color32::from_rgb(90, 200, 90),
+        3 => egui::Color32::from_rgb(255, 100, 100),
+        4 => egui::Color32::from_rgb(180, 120, 255),
+        5 => egui::Color32::from_rgb(220, 140, 60),
+        6 => egui::Color32::from_rgb(60, 200, 200),
+        _ => egui::Color32::WHITE,
+    }
+}
+
+/// Draw a single block in the layer panel.
+fn layer_cell(ui: &mut egui::Ui, block: &Block, highlight: bool) -> egui::Response {
+    const CELL_SIZE: f32 = 18.0;
+    let (rect, response) =
+        ui.allocate_exact_size(egui::Vec2::splat(CELL_SIZE), egui::Sense::click());
+    let (fill, text, text_color) = match block.revealed() {
+        Some(Contains::Mine) => (
+            egui::Color32::from_rgb(120, 20, 20),
+            "*".to_string(),
+            egui::Color32::WHITE,
+        ),
+        Some(Contains::Empty { adjacent_mines: 0 }) => (
+            egui::Color32::from_gray(30),
+            String::new(),
+            egui::Color32::WHITE,
+        ),
+        Some(Contains::Empty { adjacent_mines }) => (
+            egui::Color32::from_gray(45),
+            adjacent_mines.to_string(),
+            number_color(adjacent_mines),
+        ),
+        None => {
+            let text = match block.marking() {
+                Marking::None => "",
+                Marking::Mine => "F",
+                Marking::Maybe => "?",
+            };
+            (
+                egui::Color32::from_gray(110),
+                text.to_string(),
+                egui::Color32::from_rgb(255, 80, 80),
+            )
+        }
+    };
+    let painter = ui.painter();
+    painter.rect_filled(rect.shrink(1.0), 2.0, fill);
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        text,
+        egui::FontId::monospace(12.0),
+        text_color,
+    );
+    if highlight || response.hovered() {
+        painter.rect_stroke(
+            rect.shrink(0.5),
+            2.0,
+            egui::Stroke::new(1.5, egui::Color32::YELLOW),
+        );
+    }
+    response
+}
+
+/// Show every layer of the field as a grid. Clicking a hidden cell clears it, clicking
+/// a number chords, and right clicking marks.
+fn display_layer_panel(
+    mut contexts: EguiContexts,
+    field_settings: Res<FieldSettings>,
+    blocks: Query<&Block>,
+    hovered: Res<HoveredBlock>,
+    cursor: Res<BlockCursor>,
+    mut layer_panel: ResMut<LayerPanel>,
+    mut input_events: EventWriter<InputEvent>,
+) {
+    if !layer_panel.visible {
+        return;
+    }
+    let field_size = field_settings.field_size;
+    let blocks: HashMap<_, _> = blocks.iter().map(|block| (block.index(), block)).collect();
+    let highlighted = cursor.index.or(hovered.target.map(|(_, index)| index));
+    let axis_names = ["X", "Y", "Z"];
+    let ctx = contexts.ctx_mut();
+    egui::SidePanel::right("layers")
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Layers along:");
+                for (axis, name) in axis_names.iter().enumerate() {
+                    ui.radio_value(&mut layer_panel.axis, axis, *name);
+                }
+            });
+            let axis = layer_panel.axis;
+            // Columns and rows run along the other two axes
+            let [columns, rows] = match axis {
+                0 => [1, 2],
+                1 => [0, 2],
+                _ => [0, 1],
+            };
+            // The scroll area fills the rest of the panel, however short the window is
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let item_spacing = ui.spacing().item_spacing;
+                for layer in 0..field_size[axis] {
+                    ui.label(format!("{} = {layer}", axis_names[axis]));
+                    ui.spacing_mut().item_spacing = egui::Vec2::ZERO;
+                    for row in 0..field_size[rows] {
+                        ui.horizontal(|ui| {
+                            for column in 0..field_size[columns] {
+                                let mut index = [0; 3];
+                                index[axis] = layer;
+                                index[rows] = row;
+                                index[columns] = column;
+                                let Some(block) = blocks.get(&index) else {
+                                    continue;
+                                };
+                                let highlight = highlighted == Some(index);
+                                let response = layer_cell(ui, block, highlight);
+                                if response.clicked() {
+                                    input_events.send(if block.is_hidden() {
+                                        InputEvent::ClearAt { index }
+                                    } else {
+                                        InputEvent::ChordAt { index }
+                                    });
+                                } else if response.secondary_clicked() {
+                                    input_events.send(InputEvent::MarkAt { index });
+                                }
+                            }
+                        });
+                    }
+                    ui.spacing_mut().item_spacing = item_spacing;
+                }
+            });
+        });
+}
@@ -8,11 +8,70 @@ impl Plugin for SettingsPlugin {
     }
 }
 
-#[derive(Debug, Default, Resource)]
+#[derive(Debug, Resource)]
 pub struct GameSettings {
     /// Minefield generation constraints after first click
     pub safety: Safety,
+    /// Which surrounding blocks count as adjacent
+    pub neighborhood: Neighborhood,
+    /// Whether marking cycles through an additional "maybe" marking
+    pub maybe_marks: bool,
+    /// Whether blocks marked "maybe" are protected from being cleared
+    pub protect_maybe_marks: bool,
+    /// Number of mines that can be cleared before the game is lost
+    pub lives: usize,
+    /// Whether correctly marking every mine (and nothing else) wins the game
+    pub win_by_marking: bool,
+    /// Whether all remaining blocks are cleared once every mine is correctly marked
+    pub auto_clear_marked: bool,
+    /// Whether hovering over a revealed number fades out all blocks not adjacent to it
+    pub focus_on_hover: bool,
+    /// Whether hidden, unmarked blocks adjacent to a revealed number are highlighted
+    pub highlight_frontier: bool,
+    /// Time in seconds for the camera to ease most of the way to where it is moved,
+    /// or 0 to move instantly
+    pub camera_smoothing: f32,
+    /// Time in seconds for the camera to slow down after it is spun, or 0 to stop
+    /// as soon as rotation input stops
+    pub camera_inertia: f32,
+    /// Whether the camera uses an orthographic projection instead of perspective
+    pub orthographic: bool,
+    /// Time in seconds for a cascade of reveals to spread out from the cleared block,
+    /// or 0 to reveal everything at once
+    pub reveal_duration: f32,
+    /// Whether reveal waves, explosions and victory effects are turned off
+    pub reduced_motion: bool,
+    /// Whether wrong marks and safe blocks left next to satisfied numbers are flagged
+    /// as soon as they happen
+    pub training: bool,
 }
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            safety: Safety::default(),
+            neighborhood: Neighborhood::default(),
+            maybe_marks: false,
+            protect_maybe_marks: false,
+            lives: 1,
+            win_by_marking: false,
+            auto_clear_marked: false,
+            focus_on_hover: false,
+            highlight_frontier: false,
+            camera_smoothing: 0.08,
+            camera_inertia: 0.0,
+            orthographic: false,
+            reveal_duration: 0.4,
+            reduced_motion: false,
+            training: false,
+        }
+    }
+}
+
+/// Largest allowed size of any one field dimension.
+pub const MAX_FIELD_SIZE: usize = 20;
+/// Largest allowed size of any one dimension of a flat field, which is enough for
+/// classic Expert boards.
+pub const MAX_FLAT_FIELD_SIZE: usize = 30;
 
 #[derive(Debug, Resource, PartialEq)]
 pub struct FieldSettings {
@@ -20,24 +79,77 @@ pub struct FieldSettings {
     pub field_size: [usize; 3],
     /// Average density of mines (number of mines/number of cells)
     pub mine_density: f32,
+    /// Exact number of mines, used instead of the density if set
+    pub mine_count: Option<usize>,
+    /// Seed for mine placement in the next game only. If `None`, a new seed is chosen.
+    pub seed: Option<u32>,
 }
 impl FieldSettings {
     pub fn small() -> Self {
         Self {
             field_size: [3, 3, 3],
             mine_density: 0.2,
+            mine_count: None,
+            seed: None,
         }
     }
     pub fn medium() -> Self {
         Self {
             field_size: [5, 5, 5],
             mine_density: 0.1,
+            mine_count: None,
+            seed: None,
         }
     }
     pub fn large() -> Self {
         Self {
             field_size: [10, 10, 10],
             mine_density: 0.1,
+            mine_count: None,
+            seed: None,
+        }
+    }
+    /// Classic 2D minesweeper, 9x9 with 10 mines.
+    pub fn beginner() -> Self {
+        Self {
+            field_size: [9, 1, 9],
+            mine_density: 10.0 / 81.0,
+            mine_count: Some(10),
+            seed: None,
+        }
+    }
+    /// Classic 2D minesweeper, 16x16 with 40 mines.
+    pub fn intermediate() -> Self {
+        Self {
+            field_size: [16, 1, 16],
+            mine_density: 40.0 / 256.0,
+            mine_count: Some(40),
+            seed: None,
+        }
+    }
+    /// Classic 2D minesweeper, 30x16 with 99 mines.
+    pub fn expert() -> Self {
+        Self {
+            field_size: [30, 1, 16],
+            mine_density: 99.0 / 480.0,
+            mine_count: Some(99),
+            seed: None,
+        }
+    }
+    /// The axis along which a field is only one block thick, if any. Such a field is
+    /// played as classic 2D minesweeper, looking along this axis.
+    pub fn flat_axis(field_size: [usize; 3]) -> Option<usize> {
+        // Prefer looking down from above
+        if field_size[1] == 1 {
+            return Some(1);
+        }
+        field_size.iter().position(|size| *size == 1)
+    }
+    /// Largest size allowed for each dimension of a field, given its current size.
+    pub fn max_size(field_size: [usize; 3]) -> usize {
+        match Self::flat_axis(field_size) {
+            Some(_) => MAX_FLAT_FIELD_SIZE,
+            None => MAX_FIELD_SIZE,
         }
     }
     /// Split this struct into mutable fields that can be passed to UI elements
@@ -63,3 +175,27 @@ pub enum Safety {
     /// No guarantees - the first click could lose the game.
     Random,
 }
+
+/// Define which blocks surrounding a block are considered adjacent to it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// Blocks sharing a face, edge or corner (up to 26).
+    #[default]
+    Full,
+    /// Blocks sharing a face or edge (up to 18).
+    Edges,
+    /// Blocks sharing a face (up to 6).
+    Faces,
+}
+impl Neighborhood {
+    /// Return true iff a block at `offset` from another block is adjacent to it.
+    pub fn contains(&self, offset: [isize; 3]) -> bool {
+        let axes = offset.iter().filter(|o| **o != 0).count();
+        let max_axes = match self {
+            Self::Full => 3,
+            Self::Edges => 2,
+            Self::Faces => 1,
+        };
+        offset.iter().all(|o| o.abs() <= 1) && axes > 0 && axes <= max_axes
+    }
+}